@@ -9,10 +9,16 @@ macro_rules! spin {
 pub use spin;
 
 #[macro_export]
-/// Calls [`std::hint::black_box()`] on the given function
+/// Calls [`std::hint::black_box()`] on the given value
+///
+/// ```rust
+/// # use benri::hint::*;
+/// let x = black_box!(1 + 1);
+/// assert!(x == 2);
+/// ```
 macro_rules! black_box {
-	($function:$tt) => {
-		::std::hint::black_box($function)
+	($value:expr) => {
+		::std::hint::black_box($value)
 	}
 }
 pub use black_box;
@@ -16,6 +16,7 @@
 //!
 //! ## Feature flags
 //! - `log` - Enable [`log`](https://docs.rs/log) usage in certain places
+//! - `lock_stats` - Record per-call-site lock wait times in `sync::lock!()`, `lockr!()` and `lockw!()`. Hold times and the hold-threshold warning are only recorded by `lock_tracked!()`, `lockr_tracked!()` and `lockw_tracked!()`, since the plain macros keep returning std guards
 //!
 //! ### Example 1 - Flip a bool:
 //! ```rust
@@ -57,7 +58,7 @@
 //! let now = now!();
 //!
 //! // This sleeps the current thread for 1 second.
-//! sleep!(1000);
+//! sleep_millis!(1000);
 //!
 //! assert!(secs!(now) >= 1);
 //! ```
@@ -101,7 +102,6 @@
 	for_loops_over_fallibles,
 	large_assignments,
 	overlapping_range_endpoints,
-	private_in_public,
 	semicolon_in_expressions_from_macros,
 	redundant_semicolons,
 	unconditional_recursion,
@@ -125,10 +125,12 @@
 )]
 
 #[cfg(feature = "log")]
-#[macro_export]
 /// Logging functionality using [`log`](https://docs.rs/log)
 pub mod log;
 
+/// `std::hint::*`
+pub mod hint;
+
 /// Operators
 pub mod ops;
 
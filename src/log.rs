@@ -1,4 +1,6 @@
 //---------------------------------------------------------------------------------------------------- Use
+// These are only used for doc links.
+#![allow(unused_imports)]
 use log::{info,error,warn,trace,debug};
 
 //---------------------------------------------------------------------------------------------------- Log
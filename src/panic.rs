@@ -4,19 +4,21 @@
 ///
 /// If the `log` feature flag is enabled, this emits a
 /// message with [`log::error`] before exiting.
+///
+/// The message includes the name of the thread that called this, see [`thread_name!`](crate::thread::thread_name).
 macro_rules! mass_panic {
 	// Single argument.
 	($tts:tt) => {{
 		// Log.
 		#[cfg(feature = "log")]
 		{
-			::log::error!("----- MASS PANIC: {} @ {} -----", file!(), line!());
+			::log::error!("----- MASS PANIC: {} @ {} [{}] -----", file!(), line!(), $crate::thread_name!());
 			::log::error!("{}", $tts);
 			::log::error!("----- EXITING ALL THREADS -----");
 		}
 		#[cfg(not(feature = "log"))]
 		{
-			::std::eprintln!("----- MASS PANIC: {} @ {} -----", file!(), line!());
+			::std::eprintln!("----- MASS PANIC: {} @ {} [{}] -----", file!(), line!(), $crate::thread_name!());
 			::std::eprintln!("{}", $tts);
 			::std::eprintln!("----- EXITING ALL THREADS -----");
 		}
@@ -29,13 +31,13 @@ macro_rules! mass_panic {
 	($($tts:tt)*) => {{
 		#[cfg(feature = "log")]
 		{
-			::log::error!("----- MASS PANIC: {} @ {} -----", file!(), line!());
+			::log::error!("----- MASS PANIC: {} @ {} [{}] -----", file!(), line!(), $crate::thread_name!());
 			::log::error!("{}", ::std::format_args!($($tts)*));
 			::log::error!("----- EXITING ALL THREADS -----");
 		}
 		#[cfg(not(feature = "log"))]
 		{
-			::std::eprintln!("----- MASS PANIC: {} @ {} -----", file!(), line!());
+			::std::eprintln!("----- MASS PANIC: {} @ {} [{}] -----", file!(), line!(), $crate::thread_name!());
 			::std::eprintln!("{}", ::std::format_args!($($tts)*));
 			::std::eprintln!("----- EXITING ALL THREADS -----");
 		}
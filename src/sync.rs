@@ -1,4 +1,6 @@
 //---------------------------------------------------------------------------------------------------- Use
+// These are only used for doc links.
+#![allow(unused_imports)]
 use std::sync::atomic::*;
 use std::sync::mpsc::{
 	Sender,
@@ -8,7 +10,13 @@ use std::sync::{
 	Arc,
 	Mutex,
 	RwLock,
+	OnceLock,
 };
+use std::time::{
+	Duration,
+	Instant,
+};
+use std::fmt;
 use crate::mass_panic;
 
 //---------------------------------------------------------------------------------------------------- Creation.
@@ -58,6 +66,8 @@ pub use arc_rw;
 #[macro_export]
 /// Flip an [`AtomicBool`] in place with [`Ordering::SeqCst`].
 ///
+/// An [`Ordering`] can be passed as the 2nd argument, e.g: `atomic_flip!(a, Relaxed)`.
+///
 /// ```rust
 /// # use std::sync::atomic::*;
 /// # use benri::sync::*;
@@ -66,240 +76,1951 @@ pub use arc_rw;
 /// atomic_flip!(a);
 /// assert!(atomic_load!(a) == true);
 ///
-/// atomic_flip!(a);
+/// atomic_flip!(a, AcqRel);
 /// assert!(atomic_load!(a) == false);
 /// ```
 macro_rules! atomic_flip {
 	($b:expr) => {
 		$b.fetch_xor(true, ::std::sync::atomic::Ordering::SeqCst)
-	}
+	};
+	($b:expr, $ordering:ident) => {
+		$b.fetch_xor(true, ::std::sync::atomic::Ordering::$ordering)
+	};
 }
 pub use atomic_flip;
 
 #[macro_export]
 /// `load` a [`std::sync::atomic`] type with [`Ordering::SeqCst`]
 ///
+/// An [`Ordering`] can be passed as the 2nd argument, e.g: `atomic_load!(a, Relaxed)`.
+///
+/// `Release` and `AcqRel` are not valid for loads and fail to compile.
+///
 /// ```rust
 /// # use std::sync::atomic::*;
 /// # use benri::sync::*;
 /// let a = AtomicUsize::new(5);
 ///
 /// assert!(atomic_load!(a) == 5);
+/// assert!(atomic_load!(a, Acquire) == 5);
+/// ```
+///
+/// ```rust,compile_fail
+/// # use std::sync::atomic::*;
+/// # use benri::sync::*;
+/// let a = AtomicUsize::new(5);
+/// atomic_load!(a, Release);
 /// ```
 macro_rules! atomic_load {
 	($b:expr) => {
 		$b.load(::std::sync::atomic::Ordering::SeqCst)
-	}
+	};
+	($b:expr, Release) => {
+		::std::compile_error!("atomic_load!: `Release` is not a valid ordering for loads")
+	};
+	($b:expr, AcqRel) => {
+		::std::compile_error!("atomic_load!: `AcqRel` is not a valid ordering for loads")
+	};
+	($b:expr, $ordering:ident) => {
+		$b.load(::std::sync::atomic::Ordering::$ordering)
+	};
 }
 pub use atomic_load;
 
 #[macro_export]
 /// `fetch_add()` a [`std::sync::atomic`] type with [`Ordering::SeqCst`]
 ///
+/// An [`Ordering`] can be passed as the 3rd argument, e.g: `atomic_add!(a, 1, Relaxed)`.
+///
 /// ```rust
 /// # use std::sync::atomic::*;
 /// # use benri::sync::*;
 /// let a = AtomicUsize::new(5);
 /// atomic_add!(a, 5);
+/// atomic_add!(a, 5, Relaxed);
 ///
-/// assert!(atomic_load!(a) == 10);
+/// assert!(atomic_load!(a) == 15);
 /// ```
 macro_rules! atomic_add {
 	($atomic:expr, $i:expr) => {
 		$atomic.fetch_add($i, ::std::sync::atomic::Ordering::SeqCst)
-	}
+	};
+	($atomic:expr, $i:expr, $ordering:ident) => {
+		$atomic.fetch_add($i, ::std::sync::atomic::Ordering::$ordering)
+	};
 }
 pub use atomic_add;
 
 #[macro_export]
 /// `fetch_sub()` a [`std::sync::atomic`] type with [`Ordering::SeqCst`]
 ///
+/// An [`Ordering`] can be passed as the 3rd argument, e.g: `atomic_sub!(a, 1, Relaxed)`.
+///
 /// ```rust
 /// # use std::sync::atomic::*;
 /// # use benri::sync::*;
-/// let a = AtomicUsize::new(5);
+/// let a = AtomicUsize::new(10);
 /// atomic_sub!(a, 5);
+/// atomic_sub!(a, 5, Release);
 ///
 /// assert!(atomic_load!(a) == 0);
 /// ```
 macro_rules! atomic_sub {
 	($atomic:expr, $i:expr) => {
 		$atomic.fetch_sub($i, ::std::sync::atomic::Ordering::SeqCst)
-	}
+	};
+	($atomic:expr, $i:expr, $ordering:ident) => {
+		$atomic.fetch_sub($i, ::std::sync::atomic::Ordering::$ordering)
+	};
 }
 pub use atomic_sub;
 
 #[macro_export]
 /// `store()` a [`std::sync::atomic`] type with [`Ordering::SeqCst`]
 ///
+/// An [`Ordering`] can be passed as the 3rd argument, e.g: `atomic_store!(a, 1, Release)`.
+///
+/// `Acquire` and `AcqRel` are not valid for stores and fail to compile.
+///
 /// ```rust
 /// # use std::sync::atomic::*;
 /// # use benri::sync::*;
 /// let a = AtomicUsize::new(0);
 /// atomic_store!(a, 123);
-///
 /// assert!(atomic_load!(a) == 123);
+///
+/// atomic_store!(a, 456, Release);
+/// assert!(atomic_load!(a, Acquire) == 456);
+/// ```
+///
+/// ```rust,compile_fail
+/// # use std::sync::atomic::*;
+/// # use benri::sync::*;
+/// let a = AtomicUsize::new(0);
+/// atomic_store!(a, 123, Acquire);
 /// ```
 macro_rules! atomic_store {
 	($atomic:expr, $i:expr) => {
 		$atomic.store($i, ::std::sync::atomic::Ordering::SeqCst)
-	}
+	};
+	($atomic:expr, $i:expr, Acquire) => {
+		::std::compile_error!("atomic_store!: `Acquire` is not a valid ordering for stores")
+	};
+	($atomic:expr, $i:expr, AcqRel) => {
+		::std::compile_error!("atomic_store!: `AcqRel` is not a valid ordering for stores")
+	};
+	($atomic:expr, $i:expr, $ordering:ident) => {
+		$atomic.store($i, ::std::sync::atomic::Ordering::$ordering)
+	};
 }
 pub use atomic_store;
 
-//---------------------------------------------------------------------------------------------------- Channels
 #[macro_export]
-/// `.send()` a channel message and `.unwrap()`
+/// [`atomic_load!`] with [`Ordering::Relaxed`]
+macro_rules! atomic_load_relaxed {
+	($b:expr) => {
+		$crate::atomic_load!($b, Relaxed)
+	}
+}
+pub use atomic_load_relaxed;
+
+#[macro_export]
+/// [`atomic_load!`] with [`Ordering::Acquire`]
+macro_rules! atomic_load_acquire {
+	($b:expr) => {
+		$crate::atomic_load!($b, Acquire)
+	}
+}
+pub use atomic_load_acquire;
+
+#[macro_export]
+/// [`atomic_store!`] with [`Ordering::Relaxed`]
+macro_rules! atomic_store_relaxed {
+	($atomic:expr, $i:expr) => {
+		$crate::atomic_store!($atomic, $i, Relaxed)
+	}
+}
+pub use atomic_store_relaxed;
+
+#[macro_export]
+/// [`atomic_store!`] with [`Ordering::Release`]
+macro_rules! atomic_store_release {
+	($atomic:expr, $i:expr) => {
+		$crate::atomic_store!($atomic, $i, Release)
+	}
+}
+pub use atomic_store_release;
+
+#[macro_export]
+/// [`atomic_add!`] with [`Ordering::Relaxed`]
+macro_rules! atomic_add_relaxed {
+	($atomic:expr, $i:expr) => {
+		$crate::atomic_add!($atomic, $i, Relaxed)
+	}
+}
+pub use atomic_add_relaxed;
+
+#[macro_export]
+/// [`atomic_add!`] with [`Ordering::Acquire`]
+macro_rules! atomic_add_acquire {
+	($atomic:expr, $i:expr) => {
+		$crate::atomic_add!($atomic, $i, Acquire)
+	}
+}
+pub use atomic_add_acquire;
+
+#[macro_export]
+/// [`atomic_add!`] with [`Ordering::Release`]
+macro_rules! atomic_add_release {
+	($atomic:expr, $i:expr) => {
+		$crate::atomic_add!($atomic, $i, Release)
+	}
+}
+pub use atomic_add_release;
+
+#[macro_export]
+/// [`atomic_sub!`] with [`Ordering::Relaxed`]
+macro_rules! atomic_sub_relaxed {
+	($atomic:expr, $i:expr) => {
+		$crate::atomic_sub!($atomic, $i, Relaxed)
+	}
+}
+pub use atomic_sub_relaxed;
+
+#[macro_export]
+/// [`atomic_sub!`] with [`Ordering::Acquire`]
+macro_rules! atomic_sub_acquire {
+	($atomic:expr, $i:expr) => {
+		$crate::atomic_sub!($atomic, $i, Acquire)
+	}
+}
+pub use atomic_sub_acquire;
+
+#[macro_export]
+/// [`atomic_sub!`] with [`Ordering::Release`]
+macro_rules! atomic_sub_release {
+	($atomic:expr, $i:expr) => {
+		$crate::atomic_sub!($atomic, $i, Release)
+	}
+}
+pub use atomic_sub_release;
+
+#[macro_export]
+/// [`atomic_flip!`] with [`Ordering::Relaxed`]
+macro_rules! atomic_flip_relaxed {
+	($b:expr) => {
+		$crate::atomic_flip!($b, Relaxed)
+	}
+}
+pub use atomic_flip_relaxed;
+
+#[macro_export]
+/// [`atomic_flip!`] with [`Ordering::Acquire`]
+macro_rules! atomic_flip_acquire {
+	($b:expr) => {
+		$crate::atomic_flip!($b, Acquire)
+	}
+}
+pub use atomic_flip_acquire;
+
+#[macro_export]
+/// [`atomic_flip!`] with [`Ordering::Release`]
+macro_rules! atomic_flip_release {
+	($b:expr) => {
+		$crate::atomic_flip!($b, Release)
+	}
+}
+pub use atomic_flip_release;
+
+/// The strongest valid failure [`Ordering`] for a given success [`Ordering`]
+///
+/// This is used by [`atomic_cas!`] and [`atomic_update!`] when
+/// only one [`Ordering`] is given, and maps:
+/// - `SeqCst` -> `SeqCst`
+/// - `AcqRel`/`Acquire` -> `Acquire`
+/// - `Release`/`Relaxed` -> `Relaxed`
+pub const fn failure_ordering(success: Ordering) -> Ordering {
+	match success {
+		Ordering::AcqRel | Ordering::Acquire => Ordering::Acquire,
+		Ordering::Release | Ordering::Relaxed => Ordering::Relaxed,
+		_ => Ordering::SeqCst,
+	}
+}
+
+#[macro_export]
+/// `swap()` a [`std::sync::atomic`] type with [`Ordering::SeqCst`], returning the old value
+///
+/// An [`Ordering`] can be passed as the 3rd argument.
 ///
 /// ```rust
-/// # use benri::thread::*;
+/// # use std::sync::atomic::*;
 /// # use benri::sync::*;
-/// let (tx, rx) = std::sync::mpsc::channel::<u8>();
-///
-/// std::thread::spawn(move || {
-///     send!(tx, 255);
-/// });
+/// let a = AtomicUsize::new(5);
 ///
-/// sleep!(1000);
-/// assert!(recv!(rx) == 255);
+/// assert!(atomic_swap!(a, 10) == 5);
+/// assert!(atomic_swap!(a, 15, AcqRel) == 10);
+/// assert!(atomic_load!(a) == 15);
 /// ```
-macro_rules! send {
-	($channel:expr, $($msg:tt)*) => {
-		$channel.send($($msg)*).unwrap()
-	}
+macro_rules! atomic_swap {
+	($atomic:expr, $i:expr) => {
+		$atomic.swap($i, ::std::sync::atomic::Ordering::SeqCst)
+	};
+	($atomic:expr, $i:expr, $ordering:ident) => {
+		$atomic.swap($i, ::std::sync::atomic::Ordering::$ordering)
+	};
 }
-pub use send;
+pub use atomic_swap;
 
 #[macro_export]
-/// `.recv()` a channel message and `.unwrap()`
+/// `compare_exchange()` a [`std::sync::atomic`] type with [`Ordering::SeqCst`]
+///
+/// If the current value equals `current`, it is replaced with `new`.
+///
+/// This returns [`Ok`] with the previous value on success, or [`Err`] with the actual value on failure.
+///
+/// A success [`Ordering`] can be passed as the 4th argument, in which case the
+/// failure [`Ordering`] is picked with [`failure_ordering`](crate::sync::failure_ordering),
+/// or both can be passed as the 4th and 5th arguments.
 ///
 /// ```rust
-/// # use benri::thread::*;
+/// # use std::sync::atomic::*;
 /// # use benri::sync::*;
-/// let (tx, rx) = std::sync::mpsc::channel::<u8>();
+/// let a = AtomicUsize::new(5);
 ///
-/// std::thread::spawn(move || {
-///     send!(tx, 255);
-/// });
+/// assert!(atomic_cas!(a, 5, 10) == Ok(5));
+/// assert!(atomic_cas!(a, 5, 20) == Err(10));
+/// assert!(atomic_cas!(a, 10, 20, AcqRel) == Ok(10));
+/// assert!(atomic_cas!(a, 20, 30, Release, Relaxed) == Ok(20));
+/// ```
 ///
-/// sleep!(1000);
-/// assert!(recv!(rx) == 255);
+/// `Release` and `AcqRel` are not valid failure orderings and fail to compile:
+/// ```rust,compile_fail
+/// # use std::sync::atomic::*;
+/// # use benri::sync::*;
+/// let a = AtomicUsize::new(5);
+/// atomic_cas!(a, 5, 10, SeqCst, Release);
 /// ```
-macro_rules! recv {
-	($channel:expr) => {
-		$channel.recv().unwrap()
-	}
+macro_rules! atomic_cas {
+	($atomic:expr, $current:expr, $new:expr) => {
+		$atomic.compare_exchange(
+			$current,
+			$new,
+			::std::sync::atomic::Ordering::SeqCst,
+			::std::sync::atomic::Ordering::SeqCst,
+		)
+	};
+	($atomic:expr, $current:expr, $new:expr, $success:ident) => {
+		$atomic.compare_exchange(
+			$current,
+			$new,
+			::std::sync::atomic::Ordering::$success,
+			$crate::sync::failure_ordering(::std::sync::atomic::Ordering::$success),
+		)
+	};
+	($atomic:expr, $current:expr, $new:expr, $success:ident, Release) => {
+		::std::compile_error!("atomic_cas!: `Release` is not a valid failure ordering")
+	};
+	($atomic:expr, $current:expr, $new:expr, $success:ident, AcqRel) => {
+		::std::compile_error!("atomic_cas!: `AcqRel` is not a valid failure ordering")
+	};
+	($atomic:expr, $current:expr, $new:expr, $success:ident, $failure:ident) => {
+		$atomic.compare_exchange(
+			$current,
+			$new,
+			::std::sync::atomic::Ordering::$success,
+			::std::sync::atomic::Ordering::$failure,
+		)
+	};
 }
-pub use recv;
+pub use atomic_cas;
 
 #[macro_export]
-/// `.send()` a channel message, [`mass_panic!`] on failure
+/// `fetch_update()` a [`std::sync::atomic`] type with [`Ordering::SeqCst`]
+///
+/// The closure is called with the current value in a loop until the
+/// value is successfully replaced, or the closure returns [`None`].
+///
+/// This returns [`Ok`] with the previous value, or [`Err`] with the current value if the closure returned [`None`].
+///
+/// An [`Ordering`] can be passed as the 3rd argument, the fetch
+/// [`Ordering`] is picked with [`failure_ordering`](crate::sync::failure_ordering).
 ///
 /// ```rust
-/// # use benri::thread::*;
+/// # use std::sync::atomic::*;
 /// # use benri::sync::*;
-/// let (tx, rx) = std::sync::mpsc::channel::<u8>();
+/// let a = AtomicUsize::new(5);
 ///
-/// std::thread::spawn(move || {
-///     send_or_mass!(tx, 255);
-/// });
+/// assert!(atomic_update!(a, |x| Some(x * 2)) == Ok(5));
+/// assert!(atomic_update!(a, |x| x.checked_sub(100), AcqRel) == Err(10));
+/// assert!(atomic_load!(a) == 10);
+/// ```
+macro_rules! atomic_update {
+	($atomic:expr, $f:expr) => {
+		$atomic.fetch_update(
+			::std::sync::atomic::Ordering::SeqCst,
+			::std::sync::atomic::Ordering::SeqCst,
+			$f,
+		)
+	};
+	($atomic:expr, $f:expr, $ordering:ident) => {
+		$atomic.fetch_update(
+			::std::sync::atomic::Ordering::$ordering,
+			$crate::sync::failure_ordering(::std::sync::atomic::Ordering::$ordering),
+			$f,
+		)
+	};
+}
+pub use atomic_update;
+
+#[macro_export]
+/// `fetch_max()` a [`std::sync::atomic`] type with [`Ordering::SeqCst`]
 ///
-/// sleep!(1000);
-/// assert!(recv_or_die!(rx) == 255);
+/// Set to the maximum of the current value and the input, returning the old value.
+///
+/// An [`Ordering`] can be passed as the 3rd argument.
+///
+/// ```rust
+/// # use std::sync::atomic::*;
+/// # use benri::sync::*;
+/// let a = AtomicUsize::new(5);
+///
+/// atomic_max!(a, 10);
+/// atomic_max!(a, 1, Relaxed);
+/// assert!(atomic_load!(a) == 10);
 /// ```
-macro_rules! send_or_mass {
-	($channel:expr, $($msg:tt)*) => {
-		if let ::std::result::Result::Err(e) = $channel.send($($msg)*) {
-			#[cfg(feature = "log")]
-			::log::error!("THREAD PANIC - FAILED TO SEND: {}", e);
-			#[cfg(not(feature = "log"))]
-			::std::eprintln!("THREAD PANIC - FAILED TO SEND: {}", e);
-			$crate::mass_panic!(e);
-		}
-	}
+macro_rules! atomic_max {
+	($atomic:expr, $i:expr) => {
+		$atomic.fetch_max($i, ::std::sync::atomic::Ordering::SeqCst)
+	};
+	($atomic:expr, $i:expr, $ordering:ident) => {
+		$atomic.fetch_max($i, ::std::sync::atomic::Ordering::$ordering)
+	};
 }
-pub use send_or_mass;
+pub use atomic_max;
 
 #[macro_export]
-/// `.recv()` a channel message, [`mass_panic!`] on failure
+/// `fetch_min()` a [`std::sync::atomic`] type with [`Ordering::SeqCst`]
+///
+/// Set to the minimum of the current value and the input, returning the old value.
+///
+/// An [`Ordering`] can be passed as the 3rd argument.
 ///
 /// ```rust
-/// # use benri::thread::*;
+/// # use std::sync::atomic::*;
 /// # use benri::sync::*;
-/// let (tx, rx) = std::sync::mpsc::channel::<u8>();
+/// let a = AtomicUsize::new(5);
 ///
-/// std::thread::spawn(move || {
-///     send_or_mass!(tx, 255);
-/// });
+/// atomic_min!(a, 1);
+/// atomic_min!(a, 10, Relaxed);
+/// assert!(atomic_load!(a) == 1);
+/// ```
+macro_rules! atomic_min {
+	($atomic:expr, $i:expr) => {
+		$atomic.fetch_min($i, ::std::sync::atomic::Ordering::SeqCst)
+	};
+	($atomic:expr, $i:expr, $ordering:ident) => {
+		$atomic.fetch_min($i, ::std::sync::atomic::Ordering::$ordering)
+	};
+}
+pub use atomic_min;
+
+#[macro_export]
+/// `fetch_and()` a [`std::sync::atomic`] type with [`Ordering::SeqCst`]
+///
+/// Bitwise AND with the input, returning the old value.
+///
+/// An [`Ordering`] can be passed as the 3rd argument.
+///
+/// ```rust
+/// # use std::sync::atomic::*;
+/// # use benri::sync::*;
+/// let a = AtomicUsize::new(0b1100);
 ///
-/// sleep!(1000);
-/// assert!(recv_or_die!(rx) == 255);
+/// atomic_and!(a, 0b1010);
+/// assert!(atomic_load!(a) == 0b1000);
 /// ```
-macro_rules! recv_or_mass {
-	($channel:expr) => {
-		match $channel.recv() {
-			::std::result::Result::Ok(msg) => msg,
-			::std::result::Result::Err(e)  => {
-				#[cfg(feature = "log")]
-				::log::error!("THREAD PANIC - FAILED TO RECEIVE: {}", e);
-				#[cfg(not(feature = "log"))]
-				::std::eprintln!("THREAD PANIC - FAILED TO RECEIVE: {}", e);
-				$crate::mass_panic!(e);
-			},
-		}
-	}
+macro_rules! atomic_and {
+	($atomic:expr, $i:expr) => {
+		$atomic.fetch_and($i, ::std::sync::atomic::Ordering::SeqCst)
+	};
+	($atomic:expr, $i:expr, $ordering:ident) => {
+		$atomic.fetch_and($i, ::std::sync::atomic::Ordering::$ordering)
+	};
 }
-pub use recv_or_mass;
+pub use atomic_and;
 
-//---------------------------------------------------------------------------------------------------- Mutex/RwLock
 #[macro_export]
-/// `.lock()` a [`Mutex`] and `.unwrap()`
+/// `fetch_or()` a [`std::sync::atomic`] type with [`Ordering::SeqCst`]
+///
+/// Bitwise OR with the input, returning the old value.
+///
+/// An [`Ordering`] can be passed as the 3rd argument.
 ///
 /// ```rust
-/// # use std::sync::Mutex;
+/// # use std::sync::atomic::*;
 /// # use benri::sync::*;
-/// let a = Mutex::new(0);
+/// let a = AtomicUsize::new(0b1100);
 ///
-/// assert!(*lock!(a) == 0);
+/// atomic_or!(a, 0b1010, AcqRel);
+/// assert!(atomic_load!(a) == 0b1110);
 /// ```
-macro_rules! lock {
-	($lock:expr) => {
-		$lock.lock().unwrap()
-	}
+macro_rules! atomic_or {
+	($atomic:expr, $i:expr) => {
+		$atomic.fetch_or($i, ::std::sync::atomic::Ordering::SeqCst)
+	};
+	($atomic:expr, $i:expr, $ordering:ident) => {
+		$atomic.fetch_or($i, ::std::sync::atomic::Ordering::$ordering)
+	};
 }
-pub use lock;
+pub use atomic_or;
 
 #[macro_export]
-/// `.read()` a [`RwLock`] and `.unwrap()`
+/// `fetch_nand()` a [`std::sync::atomic`] type with [`Ordering::SeqCst`]
+///
+/// Bitwise NAND with the input, returning the old value.
+///
+/// An [`Ordering`] can be passed as the 3rd argument.
 ///
 /// ```rust
-/// # use std::sync::RwLock;
+/// # use std::sync::atomic::*;
 /// # use benri::sync::*;
-/// let a = RwLock::new(0);
+/// let a = AtomicBool::new(true);
 ///
-/// assert!(*lockr!(a) == 0);
+/// atomic_nand!(a, true);
+/// assert!(atomic_load!(a) == false);
 /// ```
-macro_rules! lockr {
-	($lock:expr) => {
-		$lock.read().unwrap()
-	}
+macro_rules! atomic_nand {
+	($atomic:expr, $i:expr) => {
+		$atomic.fetch_nand($i, ::std::sync::atomic::Ordering::SeqCst)
+	};
+	($atomic:expr, $i:expr, $ordering:ident) => {
+		$atomic.fetch_nand($i, ::std::sync::atomic::Ordering::$ordering)
+	};
 }
-pub use lockr;
+pub use atomic_nand;
 
 #[macro_export]
-/// `.write()` to a [`RwLock`] and .`unwrap()`
+/// Add `1` to a [`std::sync::atomic`] integer with [`Ordering::SeqCst`], returning the old value
+///
+/// An [`Ordering`] can be passed as the 2nd argument.
 ///
 /// ```rust
-/// # use std::sync::RwLock;
+/// # use std::sync::atomic::*;
 /// # use benri::sync::*;
-/// let a = RwLock::new(0);
-/// *lockw!(a) = 1;
+/// let a = AtomicUsize::new(0);
 ///
-/// assert!(*lockw!(a) == 1);
+/// atomic_inc!(a);
+/// atomic_inc!(a, Relaxed);
+/// assert!(atomic_load!(a) == 2);
 /// ```
-macro_rules! lockw {
-	($lock:expr) => {
-		$lock.write().unwrap()
-	}
+macro_rules! atomic_inc {
+	($atomic:expr) => {
+		$crate::atomic_add!($atomic, 1)
+	};
+	($atomic:expr, $ordering:ident) => {
+		$crate::atomic_add!($atomic, 1, $ordering)
+	};
+}
+pub use atomic_inc;
+
+#[macro_export]
+/// Subtract `1` from a [`std::sync::atomic`] integer with [`Ordering::SeqCst`], returning the old value
+///
+/// An [`Ordering`] can be passed as the 2nd argument.
+///
+/// ```rust
+/// # use std::sync::atomic::*;
+/// # use benri::sync::*;
+/// let a = AtomicUsize::new(2);
+///
+/// atomic_dec!(a);
+/// atomic_dec!(a, Relaxed);
+/// assert!(atomic_load!(a) == 0);
+/// ```
+macro_rules! atomic_dec {
+	($atomic:expr) => {
+		$crate::atomic_sub!($atomic, 1)
+	};
+	($atomic:expr, $ordering:ident) => {
+		$crate::atomic_sub!($atomic, 1, $ordering)
+	};
+}
+pub use atomic_dec;
+
+//---------------------------------------------------------------------------------------------------- Atomic floats
+// Generates an atomic float wrapping the same-sized atomic integer.
+macro_rules! impl_atomic_float {
+	($name:ident, $float:ty, $atomic:ty, $(#[$attr:meta])*) => {
+		#[doc = concat!("An [`", stringify!($float), "`] that can be shared between threads")]
+		///
+		#[doc = concat!("This bit-casts the float into an [`", stringify!($atomic), "`].")]
+		///
+		/// `load`, `store` and `fetch_add` take the same arguments as
+		/// the [`std::sync::atomic`] types, so the `atomic_*!` macros work on it.
+		$(#[$attr])*
+		pub struct $name($atomic);
+
+		impl $name {
+			#[inline]
+			#[doc = concat!("Create a new [`", stringify!($name), "`]")]
+			pub const fn new(value: $float) -> Self {
+				Self(<$atomic>::new(value.to_bits()))
+			}
+
+			#[inline]
+			/// Consume the atomic and return the contained value
+			pub fn into_inner(self) -> $float {
+				<$float>::from_bits(self.0.into_inner())
+			}
+
+			#[inline]
+			/// Load the value
+			pub fn load(&self, ordering: Ordering) -> $float {
+				<$float>::from_bits(self.0.load(ordering))
+			}
+
+			#[inline]
+			/// Store a value
+			pub fn store(&self, value: $float, ordering: Ordering) {
+				self.0.store(value.to_bits(), ordering)
+			}
+
+			#[inline]
+			/// Store a value, returning the previous value
+			pub fn swap(&self, value: $float, ordering: Ordering) -> $float {
+				<$float>::from_bits(self.0.swap(value.to_bits(), ordering))
+			}
+
+			#[inline]
+			/// Store `new` if the current value is bit-for-bit equal to `current`
+			///
+			/// This returns [`Ok`] with the previous value, or [`Err`] with the current value.
+			pub fn compare_exchange(
+				&self,
+				current: $float,
+				new: $float,
+				success: Ordering,
+				failure: Ordering,
+			) -> Result<$float, $float> {
+				self.0
+					.compare_exchange(current.to_bits(), new.to_bits(), success, failure)
+					.map(<$float>::from_bits)
+					.map_err(<$float>::from_bits)
+			}
+
+			#[inline]
+			/// Replace the value with the output of `f` in a compare-and-swap loop
+			///
+			/// This returns [`Ok`] with the previous value, or [`Err`] with the current value if `f` returned [`None`].
+			pub fn fetch_update<F>(
+				&self,
+				set_ordering: Ordering,
+				fetch_ordering: Ordering,
+				mut f: F,
+			) -> Result<$float, $float>
+			where
+				F: FnMut($float) -> Option<$float>,
+			{
+				self.0
+					.fetch_update(set_ordering, fetch_ordering, |bits| f(<$float>::from_bits(bits)).map(<$float>::to_bits))
+					.map(<$float>::from_bits)
+					.map_err(<$float>::from_bits)
+			}
+
+			#[inline]
+			/// Add to the value, returning the previous value
+			pub fn fetch_add(&self, value: $float, ordering: Ordering) -> $float {
+				self.update(ordering, |x| x + value)
+			}
+
+			#[inline]
+			/// Subtract from the value, returning the previous value
+			pub fn fetch_sub(&self, value: $float, ordering: Ordering) -> $float {
+				self.update(ordering, |x| x - value)
+			}
+
+			#[inline]
+			/// Set to the maximum of the current value and `value`, returning the previous value
+			///
+			#[doc = concat!("`NaN` is handled like [`", stringify!($float), "::max`].")]
+			pub fn fetch_max(&self, value: $float, ordering: Ordering) -> $float {
+				self.update(ordering, |x| x.max(value))
+			}
+
+			#[inline]
+			/// Set to the minimum of the current value and `value`, returning the previous value
+			///
+			#[doc = concat!("`NaN` is handled like [`", stringify!($float), "::min`].")]
+			pub fn fetch_min(&self, value: $float, ordering: Ordering) -> $float {
+				self.update(ordering, |x| x.min(value))
+			}
+
+			#[inline]
+			fn update(&self, ordering: Ordering, mut f: impl FnMut($float) -> $float) -> $float {
+				match self.fetch_update(ordering, failure_ordering(ordering), |x| Some(f(x))) {
+					Ok(x) | Err(x) => x,
+				}
+			}
+		}
+
+		impl Default for $name {
+			fn default() -> Self {
+				Self::new(0.0)
+			}
+		}
+
+		impl From<$float> for $name {
+			fn from(value: $float) -> Self {
+				Self::new(value)
+			}
+		}
+
+		impl fmt::Debug for $name {
+			fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+				fmt::Debug::fmt(&self.load(Ordering::Relaxed), f)
+			}
+		}
+	};
+}
+
+impl_atomic_float!(AtomicF32, f32, AtomicU32,
+	///
+	/// ```rust
+	/// # use benri::sync::*;
+	/// let f = AtomicF32::new(1.5);
+	///
+	/// atomic_add!(f, 1.0);
+	/// atomic_sub!(f, 0.5, Relaxed);
+	/// assert!(atomic_load!(f) == 2.0);
+	///
+	/// f.fetch_max(10.0, std::sync::atomic::Ordering::SeqCst);
+	/// f.fetch_min(-1.0, std::sync::atomic::Ordering::SeqCst);
+	/// assert!(atomic_load!(f) == -1.0);
+	/// ```
+);
+impl_atomic_float!(AtomicF64, f64, AtomicU64,
+	///
+	/// ```rust
+	/// # use benri::sync::*;
+	/// let gauge = std::sync::Arc::new(AtomicF64::new(0.0));
+	///
+	/// let handles: Vec<_> = (0..4).map(|_| {
+	/// 	let gauge = std::sync::Arc::clone(&gauge);
+	/// 	std::thread::spawn(move || for _ in 0..100 { atomic_add!(gauge, 0.5); })
+	/// }).collect();
+	/// for h in handles { h.join().unwrap(); }
+	///
+	/// assert!(atomic_load!(gauge) == 200.0);
+	/// atomic_store!(gauge, 1.25);
+	/// assert!(atomic_swap!(gauge, 0.0) == 1.25);
+	/// ```
+);
+
+//---------------------------------------------------------------------------------------------------- Atomic time
+/// A [`Duration`] that can be shared between threads
+///
+/// This is stored as nanoseconds in an [`AtomicU64`],
+/// durations longer than `u64::MAX` nanoseconds (~584 years) saturate.
+///
+/// `load`, `store` and `fetch_add` take the same arguments as
+/// the [`std::sync::atomic`] types, so the `atomic_*!` macros work on it.
+///
+/// ```rust
+/// # use benri::sync::*;
+/// # use std::time::Duration;
+/// let d = AtomicDuration::new(Duration::from_secs(1));
+///
+/// atomic_add!(d, Duration::from_millis(500));
+/// assert!(atomic_load!(d) == Duration::from_millis(1500));
+///
+/// atomic_store!(d, Duration::ZERO, Release);
+/// assert!(atomic_load!(d, Acquire) == Duration::ZERO);
+/// ```
+pub struct AtomicDuration(AtomicU64);
+
+impl AtomicDuration {
+	#[inline]
+	/// Create a new [`AtomicDuration`]
+	pub const fn new(duration: Duration) -> Self {
+		Self(AtomicU64::new(duration_to_nanos(duration)))
+	}
+
+	#[inline]
+	/// Consume the atomic and return the contained value
+	pub fn into_inner(self) -> Duration {
+		Duration::from_nanos(self.0.into_inner())
+	}
+
+	#[inline]
+	/// Load the value
+	pub fn load(&self, ordering: Ordering) -> Duration {
+		Duration::from_nanos(self.0.load(ordering))
+	}
+
+	#[inline]
+	/// Store a value
+	pub fn store(&self, duration: Duration, ordering: Ordering) {
+		self.0.store(duration_to_nanos(duration), ordering)
+	}
+
+	#[inline]
+	/// Store a value, returning the previous value
+	pub fn swap(&self, duration: Duration, ordering: Ordering) -> Duration {
+		Duration::from_nanos(self.0.swap(duration_to_nanos(duration), ordering))
+	}
+
+	#[inline]
+	/// Add to the value (saturating), returning the previous value
+	pub fn fetch_add(&self, duration: Duration, ordering: Ordering) -> Duration {
+		let nanos = duration_to_nanos(duration);
+		self.update(ordering, |x| x.saturating_add(nanos))
+	}
+
+	#[inline]
+	/// Subtract from the value (saturating at [`Duration::ZERO`]), returning the previous value
+	pub fn fetch_sub(&self, duration: Duration, ordering: Ordering) -> Duration {
+		let nanos = duration_to_nanos(duration);
+		self.update(ordering, |x| x.saturating_sub(nanos))
+	}
+
+	#[inline]
+	/// Set to the maximum of the current value and `duration`, returning the previous value
+	pub fn fetch_max(&self, duration: Duration, ordering: Ordering) -> Duration {
+		Duration::from_nanos(self.0.fetch_max(duration_to_nanos(duration), ordering))
+	}
+
+	#[inline]
+	/// Set to the minimum of the current value and `duration`, returning the previous value
+	pub fn fetch_min(&self, duration: Duration, ordering: Ordering) -> Duration {
+		Duration::from_nanos(self.0.fetch_min(duration_to_nanos(duration), ordering))
+	}
+
+	#[inline]
+	fn update(&self, ordering: Ordering, mut f: impl FnMut(u64) -> u64) -> Duration {
+		match self.0.fetch_update(ordering, failure_ordering(ordering), |x| Some(f(x))) {
+			Ok(x) | Err(x) => Duration::from_nanos(x),
+		}
+	}
+}
+
+impl Default for AtomicDuration {
+	fn default() -> Self {
+		Self::new(Duration::ZERO)
+	}
+}
+
+impl From<Duration> for AtomicDuration {
+	fn from(duration: Duration) -> Self {
+		Self::new(duration)
+	}
+}
+
+impl fmt::Debug for AtomicDuration {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt::Debug::fmt(&self.load(Ordering::Relaxed), f)
+	}
+}
+
+/// An [`Instant`] that can be shared between threads
+///
+/// This is stored as a signed nanosecond offset in an [`AtomicI64`]
+/// from a process-wide base [`Instant`], so it can represent
+/// any [`Instant`] within ~292 years of that base.
+///
+/// `load`, `store` and `fetch_add` take the same arguments as
+/// the [`std::sync::atomic`] types, so the `atomic_*!` macros work on it.
+///
+/// ```rust
+/// # use benri::sync::*;
+/// # use std::time::{Duration,Instant};
+/// let start = Instant::now();
+/// let i = AtomicInstant::new(start);
+///
+/// atomic_add!(i, Duration::from_secs(1));
+/// assert!(atomic_load!(i) == start + Duration::from_secs(1));
+///
+/// let now = Instant::now();
+/// atomic_store!(i, now, Relaxed);
+/// assert!(atomic_load!(i, Relaxed) == now);
+/// ```
+pub struct AtomicInstant(AtomicI64);
+
+impl AtomicInstant {
+	#[inline]
+	/// Create a new [`AtomicInstant`]
+	pub fn new(instant: Instant) -> Self {
+		Self(AtomicI64::new(instant_to_offset(instant)))
+	}
+
+	#[inline]
+	/// Create a new [`AtomicInstant`] set to [`now!()`](crate::now)
+	pub fn now() -> Self {
+		Self::new(crate::now!())
+	}
+
+	#[inline]
+	/// Consume the atomic and return the contained value
+	pub fn into_inner(self) -> Instant {
+		offset_to_instant(self.0.into_inner())
+	}
+
+	#[inline]
+	/// Load the value
+	pub fn load(&self, ordering: Ordering) -> Instant {
+		offset_to_instant(self.0.load(ordering))
+	}
+
+	#[inline]
+	/// Store a value
+	pub fn store(&self, instant: Instant, ordering: Ordering) {
+		self.0.store(instant_to_offset(instant), ordering)
+	}
+
+	#[inline]
+	/// Store a value, returning the previous value
+	pub fn swap(&self, instant: Instant, ordering: Ordering) -> Instant {
+		offset_to_instant(self.0.swap(instant_to_offset(instant), ordering))
+	}
+
+	#[inline]
+	/// Move the value forwards by `duration`, returning the previous value
+	pub fn fetch_add(&self, duration: Duration, ordering: Ordering) -> Instant {
+		let nanos = i64::try_from(duration.as_nanos()).unwrap_or(i64::MAX);
+		self.update(ordering, |x| x.saturating_add(nanos))
+	}
+
+	#[inline]
+	/// Move the value backwards by `duration`, returning the previous value
+	pub fn fetch_sub(&self, duration: Duration, ordering: Ordering) -> Instant {
+		let nanos = i64::try_from(duration.as_nanos()).unwrap_or(i64::MAX);
+		self.update(ordering, |x| x.saturating_sub(nanos))
+	}
+
+	#[inline]
+	/// Set to the later of the current value and `instant`, returning the previous value
+	pub fn fetch_max(&self, instant: Instant, ordering: Ordering) -> Instant {
+		offset_to_instant(self.0.fetch_max(instant_to_offset(instant), ordering))
+	}
+
+	#[inline]
+	/// Set to the earlier of the current value and `instant`, returning the previous value
+	pub fn fetch_min(&self, instant: Instant, ordering: Ordering) -> Instant {
+		offset_to_instant(self.0.fetch_min(instant_to_offset(instant), ordering))
+	}
+
+	#[inline]
+	/// Time elapsed since the stored [`Instant`], loaded with `ordering`
+	pub fn elapsed(&self, ordering: Ordering) -> Duration {
+		crate::time::elapsed(self.load(ordering))
+	}
+
+	#[inline]
+	fn update(&self, ordering: Ordering, mut f: impl FnMut(i64) -> i64) -> Instant {
+		match self.0.fetch_update(ordering, failure_ordering(ordering), |x| Some(f(x))) {
+			Ok(x) | Err(x) => offset_to_instant(x),
+		}
+	}
+}
+
+impl From<Instant> for AtomicInstant {
+	fn from(instant: Instant) -> Self {
+		Self::new(instant)
+	}
+}
+
+impl fmt::Debug for AtomicInstant {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt::Debug::fmt(&self.load(Ordering::Relaxed), f)
+	}
+}
+
+#[inline]
+const fn duration_to_nanos(duration: Duration) -> u64 {
+	let nanos = duration.as_nanos();
+	if nanos > u64::MAX as u128 {
+		u64::MAX
+	} else {
+		nanos as u64
+	}
+}
+
+// The point all `AtomicInstant` offsets are relative to.
+fn instant_base() -> Instant {
+	static BASE: OnceLock<Instant> = OnceLock::new();
+	*BASE.get_or_init(Instant::now)
+}
+
+fn instant_to_offset(instant: Instant) -> i64 {
+	let base = instant_base();
+	match instant.checked_duration_since(base) {
+		Some(d) => i64::try_from(d.as_nanos()).unwrap_or(i64::MAX),
+		None => i64::try_from(base.duration_since(instant).as_nanos()).map_or(i64::MIN, |n| -n),
+	}
+}
+
+fn offset_to_instant(offset: i64) -> Instant {
+	let base = instant_base();
+	let d = Duration::from_nanos(offset.unsigned_abs());
+	if offset >= 0 {
+		base + d
+	} else {
+		base - d
+	}
+}
+
+//---------------------------------------------------------------------------------------------------- Channels
+#[macro_export]
+/// `.send()` a channel message and `.unwrap()`
+///
+/// ```rust
+/// # use benri::thread::*;
+/// # use benri::sync::*;
+/// let (tx, rx) = std::sync::mpsc::channel::<u8>();
+///
+/// std::thread::spawn(move || {
+///     send!(tx, 255);
+/// });
+///
+/// sleep!(1);
+/// assert!(recv!(rx) == 255);
+/// ```
+macro_rules! send {
+	($channel:expr, $($msg:tt)*) => {
+		$channel.send($($msg)*).unwrap()
+	}
+}
+pub use send;
+
+#[macro_export]
+/// `.recv()` a channel message and `.unwrap()`
+///
+/// ```rust
+/// # use benri::thread::*;
+/// # use benri::sync::*;
+/// let (tx, rx) = std::sync::mpsc::channel::<u8>();
+///
+/// std::thread::spawn(move || {
+///     send!(tx, 255);
+/// });
+///
+/// sleep!(1);
+/// assert!(recv!(rx) == 255);
+/// ```
+macro_rules! recv {
+	($channel:expr) => {
+		$channel.recv().unwrap()
+	}
+}
+pub use recv;
+
+#[macro_export]
+/// `.send()` a channel message, [`mass_panic!`] on failure
+///
+/// ```rust
+/// # use benri::thread::*;
+/// # use benri::sync::*;
+/// let (tx, rx) = std::sync::mpsc::channel::<u8>();
+///
+/// std::thread::spawn(move || {
+///     send_or_mass!(tx, 255);
+/// });
+///
+/// sleep!(1);
+/// assert!(recv_or_mass!(rx) == 255);
+/// ```
+macro_rules! send_or_mass {
+	($channel:expr, $($msg:tt)*) => {
+		if let ::std::result::Result::Err(e) = $channel.send($($msg)*) {
+			#[cfg(feature = "log")]
+			::log::error!("THREAD PANIC [{}] - FAILED TO SEND: {}", $crate::thread_name!(), e);
+			#[cfg(not(feature = "log"))]
+			::std::eprintln!("THREAD PANIC [{}] - FAILED TO SEND: {}", $crate::thread_name!(), e);
+			$crate::mass_panic!(e);
+		}
+	}
+}
+pub use send_or_mass;
+
+#[macro_export]
+/// `.recv()` a channel message, [`mass_panic!`] on failure
+///
+/// ```rust
+/// # use benri::thread::*;
+/// # use benri::sync::*;
+/// let (tx, rx) = std::sync::mpsc::channel::<u8>();
+///
+/// std::thread::spawn(move || {
+///     send_or_mass!(tx, 255);
+/// });
+///
+/// sleep!(1);
+/// assert!(recv_or_mass!(rx) == 255);
+/// ```
+macro_rules! recv_or_mass {
+	($channel:expr) => {
+		match $channel.recv() {
+			::std::result::Result::Ok(msg) => msg,
+			::std::result::Result::Err(e)  => {
+				#[cfg(feature = "log")]
+				::log::error!("THREAD PANIC [{}] - FAILED TO RECEIVE: {}", $crate::thread_name!(), e);
+				#[cfg(not(feature = "log"))]
+				::std::eprintln!("THREAD PANIC [{}] - FAILED TO RECEIVE: {}", $crate::thread_name!(), e);
+				$crate::mass_panic!(e);
+			},
+		}
+	}
+}
+pub use recv_or_mass;
+
+//---------------------------------------------------------------------------------------------------- Mutex/RwLock
+#[macro_export]
+/// `.lock()` a [`Mutex`] and `.unwrap()`
+///
+/// With the `lock_stats` feature flag, the time spent waiting for the lock is recorded in `lock_stats()`.
+///
+/// The hold time is _not_ recorded, since that needs a wrapper around the guard,
+/// use [`lock_tracked!`](crate::sync::lock_tracked) for that.
+///
+/// ```rust
+/// # use std::sync::Mutex;
+/// # use benri::sync::*;
+/// let a = Mutex::new(0);
+///
+/// assert!(*lock!(a) == 0);
+///
+/// // This is always a real `MutexGuard`.
+/// let cv = std::sync::Condvar::new();
+/// let (guard, _) = cv.wait_timeout(lock!(a), std::time::Duration::from_millis(1)).unwrap();
+/// ```
+macro_rules! lock {
+	($lock:expr) => {{
+		static SITE: $crate::sync::LockCounter = $crate::sync::LockCounter::new(file!(), line!());
+		let start = SITE.start();
+		SITE.acquired(start, $lock.lock().unwrap())
+	}}
+}
+pub use lock;
+
+#[macro_export]
+/// `.read()` a [`RwLock`] and `.unwrap()`
+///
+/// With the `lock_stats` feature flag, the time spent waiting for the lock is recorded in `lock_stats()`.
+///
+/// The hold time is _not_ recorded, since that needs a wrapper around the guard,
+/// use [`lockr_tracked!`](crate::sync::lockr_tracked) for that.
+///
+/// ```rust
+/// # use std::sync::RwLock;
+/// # use benri::sync::*;
+/// let a = RwLock::new(0);
+///
+/// assert!(*lockr!(a) == 0);
+/// ```
+macro_rules! lockr {
+	($lock:expr) => {{
+		static SITE: $crate::sync::LockCounter = $crate::sync::LockCounter::new(file!(), line!());
+		let start = SITE.start();
+		SITE.acquired(start, $lock.read().unwrap())
+	}}
+}
+pub use lockr;
+
+#[macro_export]
+/// `.write()` to a [`RwLock`] and .`unwrap()`
+///
+/// With the `lock_stats` feature flag, the time spent waiting for the lock is recorded in `lock_stats()`.
+///
+/// The hold time is _not_ recorded, since that needs a wrapper around the guard,
+/// use [`lockw_tracked!`](crate::sync::lockw_tracked) for that.
+///
+/// ```rust
+/// # use std::sync::RwLock;
+/// # use benri::sync::*;
+/// let a = RwLock::new(0);
+/// *lockw!(a) = 1;
+///
+/// assert!(*lockw!(a) == 1);
+/// ```
+macro_rules! lockw {
+	($lock:expr) => {{
+		static SITE: $crate::sync::LockCounter = $crate::sync::LockCounter::new(file!(), line!());
+		let start = SITE.start();
+		SITE.acquired(start, $lock.write().unwrap())
+	}}
+}
+pub use lockw;
+
+#[macro_export]
+/// `.try_lock()` a [`Mutex`], returning [`None`] if it is already locked
+///
+/// Like [`lock!`], this panics if the [`Mutex`] is poisoned.
+///
+/// ```rust
+/// # use std::sync::Mutex;
+/// # use benri::sync::*;
+/// let a = Mutex::new(0);
+///
+/// let guard = try_lock!(a);
+/// assert!(guard.is_some());
+/// assert!(try_lock!(a).is_none());
+/// ```
+macro_rules! try_lock {
+	($lock:expr) => {
+		match $lock.try_lock() {
+			::std::result::Result::Ok(guard) => ::std::option::Option::Some(guard),
+			::std::result::Result::Err(::std::sync::TryLockError::WouldBlock) => ::std::option::Option::None,
+			::std::result::Result::Err(::std::sync::TryLockError::Poisoned(e)) => ::std::panic!("{}", e),
+		}
+	}
+}
+pub use try_lock;
+
+#[macro_export]
+/// `.try_read()` a [`RwLock`], returning [`None`] if it is write-locked
+///
+/// Like [`lockr!`], this panics if the [`RwLock`] is poisoned.
+///
+/// ```rust
+/// # use std::sync::RwLock;
+/// # use benri::sync::*;
+/// let a = RwLock::new(0);
+///
+/// let guard = try_lockr!(a);
+/// assert!(try_lockr!(a).is_some());
+/// assert!(try_lockw!(a).is_none());
+/// ```
+macro_rules! try_lockr {
+	($lock:expr) => {
+		match $lock.try_read() {
+			::std::result::Result::Ok(guard) => ::std::option::Option::Some(guard),
+			::std::result::Result::Err(::std::sync::TryLockError::WouldBlock) => ::std::option::Option::None,
+			::std::result::Result::Err(::std::sync::TryLockError::Poisoned(e)) => ::std::panic!("{}", e),
+		}
+	}
+}
+pub use try_lockr;
+
+#[macro_export]
+/// `.try_write()` to a [`RwLock`], returning [`None`] if it is locked
+///
+/// Like [`lockw!`], this panics if the [`RwLock`] is poisoned.
+///
+/// ```rust
+/// # use std::sync::RwLock;
+/// # use benri::sync::*;
+/// let a = RwLock::new(0);
+///
+/// *try_lockw!(a).unwrap() = 1;
+/// assert!(*lockr!(a) == 1);
+/// ```
+macro_rules! try_lockw {
+	($lock:expr) => {
+		match $lock.try_write() {
+			::std::result::Result::Ok(guard) => ::std::option::Option::Some(guard),
+			::std::result::Result::Err(::std::sync::TryLockError::WouldBlock) => ::std::option::Option::None,
+			::std::result::Result::Err(::std::sync::TryLockError::Poisoned(e)) => ::std::panic!("{}", e),
+		}
+	}
+}
+pub use try_lockw;
+
+#[macro_export]
+/// `.lock()` a [`Mutex`], recovering the guard if it is poisoned
+///
+/// If another thread panicked while holding the lock, a warning is
+/// logged and the guard is taken out of the [`std::sync::PoisonError`].
+///
+/// ```rust
+/// # use std::sync::{Arc,Mutex};
+/// # use benri::sync::*;
+/// let a = Arc::new(Mutex::new(0));
+///
+/// let b = Arc::clone(&a);
+/// let _ = std::thread::spawn(move || {
+///     let _guard = lock!(b);
+///     panic!();
+/// }).join();
+///
+/// assert!(a.is_poisoned());
+/// assert!(*lock_or_recover!(a) == 0);
+/// ```
+macro_rules! lock_or_recover {
+	($lock:expr) => {
+		match $lock.lock() {
+			::std::result::Result::Ok(guard) => guard,
+			::std::result::Result::Err(e) => {
+				#[cfg(feature = "log")]
+				::log::warn!("[{}] RECOVERING POISONED LOCK @ {}:{}", $crate::thread_name!(), file!(), line!());
+				#[cfg(not(feature = "log"))]
+				::std::eprintln!("[{}] RECOVERING POISONED LOCK @ {}:{}", $crate::thread_name!(), file!(), line!());
+				e.into_inner()
+			},
+		}
+	}
+}
+pub use lock_or_recover;
+
+#[macro_export]
+/// `.read()` a [`RwLock`], recovering the guard if it is poisoned
+///
+/// See [`lock_or_recover!`].
+///
+/// ```rust
+/// # use std::sync::RwLock;
+/// # use benri::sync::*;
+/// let a = RwLock::new(0);
+///
+/// assert!(*lockr_or_recover!(a) == 0);
+/// ```
+macro_rules! lockr_or_recover {
+	($lock:expr) => {
+		match $lock.read() {
+			::std::result::Result::Ok(guard) => guard,
+			::std::result::Result::Err(e) => {
+				#[cfg(feature = "log")]
+				::log::warn!("[{}] RECOVERING POISONED LOCK @ {}:{}", $crate::thread_name!(), file!(), line!());
+				#[cfg(not(feature = "log"))]
+				::std::eprintln!("[{}] RECOVERING POISONED LOCK @ {}:{}", $crate::thread_name!(), file!(), line!());
+				e.into_inner()
+			},
+		}
+	}
+}
+pub use lockr_or_recover;
+
+#[macro_export]
+/// `.write()` to a [`RwLock`], recovering the guard if it is poisoned
+///
+/// See [`lock_or_recover!`].
+///
+/// ```rust
+/// # use std::sync::RwLock;
+/// # use benri::sync::*;
+/// let a = RwLock::new(0);
+/// *lockw_or_recover!(a) = 1;
+///
+/// assert!(*lockr_or_recover!(a) == 1);
+/// ```
+macro_rules! lockw_or_recover {
+	($lock:expr) => {
+		match $lock.write() {
+			::std::result::Result::Ok(guard) => guard,
+			::std::result::Result::Err(e) => {
+				#[cfg(feature = "log")]
+				::log::warn!("[{}] RECOVERING POISONED LOCK @ {}:{}", $crate::thread_name!(), file!(), line!());
+				#[cfg(not(feature = "log"))]
+				::std::eprintln!("[{}] RECOVERING POISONED LOCK @ {}:{}", $crate::thread_name!(), file!(), line!());
+				e.into_inner()
+			},
+		}
+	}
+}
+pub use lockw_or_recover;
+
+#[macro_export]
+/// `.lock()` a [`Mutex`], [`mass_panic!`] if it is poisoned
+///
+/// ```rust
+/// # use std::sync::Mutex;
+/// # use benri::sync::*;
+/// let a = Mutex::new(0);
+///
+/// assert!(*lock_or_mass!(a) == 0);
+/// ```
+macro_rules! lock_or_mass {
+	($lock:expr) => {
+		match $lock.lock() {
+			::std::result::Result::Ok(guard) => guard,
+			::std::result::Result::Err(e) => {
+				#[cfg(feature = "log")]
+				::log::error!("THREAD PANIC [{}] - POISONED LOCK: {}", $crate::thread_name!(), e);
+				#[cfg(not(feature = "log"))]
+				::std::eprintln!("THREAD PANIC [{}] - POISONED LOCK: {}", $crate::thread_name!(), e);
+				$crate::mass_panic!(e);
+			},
+		}
+	}
+}
+pub use lock_or_mass;
+
+#[macro_export]
+/// `.read()` a [`RwLock`], [`mass_panic!`] if it is poisoned
+///
+/// ```rust
+/// # use std::sync::RwLock;
+/// # use benri::sync::*;
+/// let a = RwLock::new(0);
+///
+/// assert!(*lockr_or_mass!(a) == 0);
+/// ```
+macro_rules! lockr_or_mass {
+	($lock:expr) => {
+		match $lock.read() {
+			::std::result::Result::Ok(guard) => guard,
+			::std::result::Result::Err(e) => {
+				#[cfg(feature = "log")]
+				::log::error!("THREAD PANIC [{}] - POISONED LOCK: {}", $crate::thread_name!(), e);
+				#[cfg(not(feature = "log"))]
+				::std::eprintln!("THREAD PANIC [{}] - POISONED LOCK: {}", $crate::thread_name!(), e);
+				$crate::mass_panic!(e);
+			},
+		}
+	}
+}
+pub use lockr_or_mass;
+
+#[macro_export]
+/// `.write()` to a [`RwLock`], [`mass_panic!`] if it is poisoned
+///
+/// ```rust
+/// # use std::sync::RwLock;
+/// # use benri::sync::*;
+/// let a = RwLock::new(0);
+/// *lockw_or_mass!(a) = 1;
+///
+/// assert!(*lockr_or_mass!(a) == 1);
+/// ```
+macro_rules! lockw_or_mass {
+	($lock:expr) => {
+		match $lock.write() {
+			::std::result::Result::Ok(guard) => guard,
+			::std::result::Result::Err(e) => {
+				#[cfg(feature = "log")]
+				::log::error!("THREAD PANIC [{}] - POISONED LOCK: {}", $crate::thread_name!(), e);
+				#[cfg(not(feature = "log"))]
+				::std::eprintln!("THREAD PANIC [{}] - POISONED LOCK: {}", $crate::thread_name!(), e);
+				$crate::mass_panic!(e);
+			},
+		}
+	}
+}
+pub use lockw_or_mass;
+
+/// How many times [`try_lock_until`] spins before it starts sleeping
+const LOCK_SPINS: u32 = 64;
+/// The longest [`try_lock_until`] sleeps between attempts
+const LOCK_MAX_SLEEP: Duration = Duration::from_millis(1);
+
+/// Repeatedly call a `try_lock`-style function until it succeeds or `timeout` passes
+///
+/// This spins for a short while, then sleeps with an increasing
+/// (capped) delay between attempts. This is what
+/// [`lock_timeout!`](crate::sync::lock_timeout) and friends use.
+///
+/// This returns [`None`] if the lock could not be acquired in time.
+///
+/// # Panics
+/// If `f` returns [`TryLockError::Poisoned`](std::sync::TryLockError::Poisoned).
+///
+/// ```rust
+/// # use std::sync::Mutex;
+/// # use std::time::Duration;
+/// # use benri::sync::*;
+/// let a = Mutex::new(0);
+///
+/// let guard = try_lock_until(Duration::from_millis(10), || a.try_lock());
+/// assert!(guard.is_some());
+/// ```
+pub fn try_lock_until<G, F>(timeout: Duration, mut f: F) -> Option<G>
+where
+	F: FnMut() -> Result<G, std::sync::TryLockError<G>>,
+{
+	let deadline = crate::time::Deadline::after(timeout);
+	let mut spins = 0;
+	let mut sleep = Duration::from_micros(10);
+
+	loop {
+		match f() {
+			Ok(guard) => return Some(guard),
+			Err(std::sync::TryLockError::Poisoned(e)) => panic!("{e}"),
+			Err(std::sync::TryLockError::WouldBlock) => (),
+		}
+
+		let remaining = deadline.remaining();
+		if remaining.is_zero() {
+			return None;
+		}
+
+		if spins < LOCK_SPINS {
+			spins += 1;
+			std::hint::spin_loop();
+		} else {
+			crate::time::clock_sleep(remaining.min(sleep));
+			sleep = (sleep * 2).min(LOCK_MAX_SLEEP);
+		}
+	}
+}
+
+#[macro_export]
+/// `.lock()` a [`Mutex`], giving up after a [`std::time::Duration`]
+///
+/// This spins, then sleeps between attempts until the lock is
+/// acquired or the timeout passes, returning an [`Option`] of the guard.
+///
+/// Like [`lock!`], this panics if the [`Mutex`] is poisoned.
+///
+/// ```rust
+/// # use std::sync::Mutex;
+/// # use std::time::Duration;
+/// # use benri::sync::*;
+/// let a = Mutex::new(0);
+///
+/// let guard = lock_timeout!(a, Duration::from_millis(10));
+/// assert!(guard.is_some());
+/// assert!(lock_timeout!(a, Duration::from_millis(10)).is_none());
+/// ```
+macro_rules! lock_timeout {
+	($lock:expr, $timeout:expr) => {
+		$crate::sync::try_lock_until($timeout, || $lock.try_lock())
+	}
+}
+pub use lock_timeout;
+
+#[macro_export]
+/// `.read()` a [`RwLock`], giving up after a [`std::time::Duration`]
+///
+/// See [`lock_timeout!`].
+///
+/// ```rust
+/// # use std::sync::RwLock;
+/// # use std::time::Duration;
+/// # use benri::sync::*;
+/// let a = RwLock::new(0);
+///
+/// let guard = lockw!(a);
+/// assert!(lockr_timeout!(a, Duration::from_millis(10)).is_none());
+/// ```
+macro_rules! lockr_timeout {
+	($lock:expr, $timeout:expr) => {
+		$crate::sync::try_lock_until($timeout, || $lock.try_read())
+	}
+}
+pub use lockr_timeout;
+
+#[macro_export]
+/// `.write()` to a [`RwLock`], giving up after a [`std::time::Duration`]
+///
+/// See [`lock_timeout!`].
+///
+/// ```rust
+/// # use std::sync::RwLock;
+/// # use std::time::Duration;
+/// # use benri::sync::*;
+/// let a = RwLock::new(0);
+///
+/// *lockw_timeout!(a, Duration::from_millis(10)).unwrap() = 1;
+/// assert!(*lockr!(a) == 1);
+/// ```
+macro_rules! lockw_timeout {
+	($lock:expr, $timeout:expr) => {
+		$crate::sync::try_lock_until($timeout, || $lock.try_write())
+	}
+}
+pub use lockw_timeout;
+
+
+//---------------------------------------------------------------------------------------------------- Lock stats
+#[derive(Debug)]
+#[doc(hidden)]
+/// Per-call-site lock counters, created by [`lock!`] and friends
+///
+/// Without the `lock_stats` feature flag, this does nothing.
+pub struct LockCounter {
+	#[cfg(feature = "lock_stats")]
+	file: &'static str,
+	#[cfg(feature = "lock_stats")]
+	line: u32,
+	#[cfg(feature = "lock_stats")]
+	registered: AtomicBool,
+	#[cfg(feature = "lock_stats")]
+	count: AtomicU64,
+	#[cfg(feature = "lock_stats")]
+	total_wait: AtomicU64,
+	#[cfg(feature = "lock_stats")]
+	max_wait: AtomicU64,
+	#[cfg(feature = "lock_stats")]
+	holds: AtomicU64,
+	#[cfg(feature = "lock_stats")]
+	total_hold: AtomicU64,
+	#[cfg(feature = "lock_stats")]
+	max_hold: AtomicU64,
+}
+
+impl LockCounter {
+	#[cfg_attr(not(feature = "lock_stats"), allow(unused_variables))]
+	/// Create the counters for the call site at `file:line`
+	pub const fn new(file: &'static str, line: u32) -> Self {
+		Self {
+			#[cfg(feature = "lock_stats")]
+			file,
+			#[cfg(feature = "lock_stats")]
+			line,
+			#[cfg(feature = "lock_stats")]
+			registered: AtomicBool::new(false),
+			#[cfg(feature = "lock_stats")]
+			count: AtomicU64::new(0),
+			#[cfg(feature = "lock_stats")]
+			total_wait: AtomicU64::new(0),
+			#[cfg(feature = "lock_stats")]
+			max_wait: AtomicU64::new(0),
+			#[cfg(feature = "lock_stats")]
+			holds: AtomicU64::new(0),
+			#[cfg(feature = "lock_stats")]
+			total_hold: AtomicU64::new(0),
+			#[cfg(feature = "lock_stats")]
+			max_hold: AtomicU64::new(0),
+		}
+	}
+
+	#[inline]
+	/// Called right before waiting for the lock
+	pub fn start(&self) -> Option<Instant> {
+		cfg!(feature = "lock_stats").then(Instant::now)
+	}
+
+	#[inline]
+	#[cfg_attr(not(feature = "lock_stats"), allow(unused_variables))]
+	/// Called with the acquired `guard`, this records the wait time
+	pub fn acquired<G>(&'static self, start: Option<Instant>, guard: G) -> G {
+		#[cfg(feature = "lock_stats")]
+		if let Some(start) = start {
+			self.record_wait(start.elapsed());
+		}
+		guard
+	}
+
+	#[inline]
+	/// Same as [`LockCounter::acquired`], but also records the hold time once the guard drops
+	pub fn tracked<G>(&'static self, start: Option<Instant>, guard: G) -> TrackedGuard<G> {
+		TrackedGuard {
+			guard: Some(self.acquired(start, guard)),
+			#[cfg(feature = "lock_stats")]
+			site: self,
+			#[cfg(feature = "lock_stats")]
+			acquired: Instant::now(),
+		}
+	}
+
+	#[cfg(feature = "lock_stats")]
+	fn record_wait(&'static self, wait: Duration) {
+		// Only the first call at this site takes the registry lock.
+		if !self.registered.load(Ordering::Relaxed) && !self.registered.swap(true, Ordering::Relaxed) {
+			LOCK_SITES.lock().unwrap_or_else(std::sync::PoisonError::into_inner).push(self);
+		}
+
+		let wait = duration_to_nanos(wait);
+		self.count.fetch_add(1, Ordering::Relaxed);
+		self.total_wait.fetch_add(wait, Ordering::Relaxed);
+		self.max_wait.fetch_max(wait, Ordering::Relaxed);
+	}
+
+	#[cfg(feature = "lock_stats")]
+	fn record_hold(&self, hold: Duration) {
+		let nanos = duration_to_nanos(hold);
+		self.holds.fetch_add(1, Ordering::Relaxed);
+		self.total_hold.fetch_add(nanos, Ordering::Relaxed);
+		self.max_hold.fetch_max(nanos, Ordering::Relaxed);
+
+		#[cfg(feature = "log")]
+		{
+			let threshold = lock_hold_threshold();
+			if !threshold.is_zero() && hold > threshold {
+				log::warn!("lock held for {:?} (threshold {:?}) @ {}:{}", hold, threshold, self.file, self.line);
+			}
+		}
+	}
+
+	#[cfg(feature = "lock_stats")]
+	fn snapshot(&self) -> LockSite {
+		let load = |a: &AtomicU64| a.load(Ordering::Relaxed);
+		LockSite {
+			file: self.file,
+			line: self.line,
+			count: load(&self.count),
+			total_wait: Duration::from_nanos(load(&self.total_wait)),
+			max_wait: Duration::from_nanos(load(&self.max_wait)),
+			holds: load(&self.holds),
+			total_hold: Duration::from_nanos(load(&self.total_hold)),
+			max_hold: Duration::from_nanos(load(&self.max_hold)),
+		}
+	}
+
+	#[cfg(feature = "lock_stats")]
+	fn reset(&self) {
+		for a in [&self.count, &self.total_wait, &self.max_wait, &self.holds, &self.total_hold, &self.max_hold] {
+			a.store(0, Ordering::Relaxed);
+		}
+	}
+}
+
+#[derive(Debug)]
+/// A lock guard that records how long it was held, returned by [`lock_tracked!`] and friends
+///
+/// This dereferences to the locked value. Use [`TrackedGuard::into_inner`]
+/// if the real guard is needed, e.g. for [`std::sync::Condvar::wait`].
+///
+/// Without the `lock_stats` feature flag, this records nothing.
+pub struct TrackedGuard<G> {
+	// Only `None` after `into_inner()` or during `drop()`.
+	guard: Option<G>,
+	#[cfg(feature = "lock_stats")]
+	site: &'static LockCounter,
+	#[cfg(feature = "lock_stats")]
+	acquired: Instant,
+}
+
+impl<G> TrackedGuard<G> {
+	/// Record the hold time so far and return the inner guard
+	///
+	/// Time held after this is not recorded.
+	pub fn into_inner(mut self) -> G {
+		#[cfg(feature = "lock_stats")]
+		self.site.record_hold(self.acquired.elapsed());
+		match self.guard.take() {
+			Some(guard) => guard,
+			None => unreachable!(),
+		}
+	}
+}
+
+impl<G: std::ops::Deref> std::ops::Deref for TrackedGuard<G> {
+	type Target = G::Target;
+
+	fn deref(&self) -> &Self::Target {
+		match &self.guard {
+			Some(guard) => guard,
+			None => unreachable!(),
+		}
+	}
+}
+
+impl<G: std::ops::DerefMut> std::ops::DerefMut for TrackedGuard<G> {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		match &mut self.guard {
+			Some(guard) => guard,
+			None => unreachable!(),
+		}
+	}
+}
+
+impl<G> Drop for TrackedGuard<G> {
+	fn drop(&mut self) {
+		if let Some(guard) = self.guard.take() {
+			// Release the lock before recording.
+			#[cfg(feature = "lock_stats")]
+			let hold = self.acquired.elapsed();
+			drop(guard);
+			#[cfg(feature = "lock_stats")]
+			self.site.record_hold(hold);
+		}
+	}
+}
+
+#[macro_export]
+/// [`lock!`], returning a [`TrackedGuard`](crate::sync::TrackedGuard) that also records the hold time
+///
+/// With the `lock_stats` feature flag, the wait and hold time are recorded
+/// in `lock_stats()`, and holds longer than `set_lock_hold_threshold()`
+/// are warned about with the `log` feature flag.
+///
+/// ```rust
+/// # use std::sync::Mutex;
+/// # use benri::sync::*;
+/// let a = Mutex::new(0);
+/// *lock_tracked!(a) += 1;
+///
+/// assert!(*lock_tracked!(a) == 1);
+/// ```
+macro_rules! lock_tracked {
+	($lock:expr) => {{
+		static SITE: $crate::sync::LockCounter = $crate::sync::LockCounter::new(file!(), line!());
+		let start = SITE.start();
+		SITE.tracked(start, $lock.lock().unwrap())
+	}}
+}
+pub use lock_tracked;
+
+#[macro_export]
+/// [`lockr!`], returning a [`TrackedGuard`](crate::sync::TrackedGuard) that also records the hold time
+///
+/// See [`lock_tracked!`].
+///
+/// ```rust
+/// # use std::sync::RwLock;
+/// # use benri::sync::*;
+/// let a = RwLock::new(0);
+///
+/// assert!(*lockr_tracked!(a) == 0);
+/// ```
+macro_rules! lockr_tracked {
+	($lock:expr) => {{
+		static SITE: $crate::sync::LockCounter = $crate::sync::LockCounter::new(file!(), line!());
+		let start = SITE.start();
+		SITE.tracked(start, $lock.read().unwrap())
+	}}
+}
+pub use lockr_tracked;
+
+#[macro_export]
+/// [`lockw!`], returning a [`TrackedGuard`](crate::sync::TrackedGuard) that also records the hold time
+///
+/// See [`lock_tracked!`].
+///
+/// ```rust
+/// # use std::sync::RwLock;
+/// # use benri::sync::*;
+/// let a = RwLock::new(0);
+/// *lockw_tracked!(a) = 1;
+///
+/// assert!(*lockr!(a) == 1);
+/// ```
+macro_rules! lockw_tracked {
+	($lock:expr) => {{
+		static SITE: $crate::sync::LockCounter = $crate::sync::LockCounter::new(file!(), line!());
+		let start = SITE.start();
+		SITE.tracked(start, $lock.write().unwrap())
+	}}
+}
+pub use lockw_tracked;
+
+// Every call site that has recorded something.
+#[cfg(feature = "lock_stats")]
+static LOCK_SITES: Mutex<Vec<&LockCounter>> = Mutex::new(Vec::new());
+
+#[cfg(feature = "lock_stats")]
+static LOCK_HOLD_THRESHOLD: AtomicDuration = AtomicDuration::new(Duration::ZERO);
+
+#[cfg(feature = "lock_stats")]
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+/// Wait and hold times recorded for a single lock call site
+pub struct LockSite {
+	/// The file the lock was taken in
+	pub file: &'static str,
+	/// The line the lock was taken on
+	pub line: u32,
+	/// How many times the lock was taken
+	pub count: u64,
+	/// Total time spent waiting to acquire the lock
+	pub total_wait: Duration,
+	/// Longest single wait to acquire the lock
+	pub max_wait: Duration,
+	/// How many hold times were recorded, only [`lock_tracked!`] and friends record these
+	pub holds: u64,
+	/// Total time the lock was held
+	pub total_hold: Duration,
+	/// Longest single time the lock was held
+	pub max_hold: Duration,
+}
+
+#[cfg(feature = "lock_stats")]
+#[derive(Clone,Debug,Default,PartialEq,Eq)]
+/// A snapshot of all recorded [`LockSite`]s, returned by [`lock_stats()`]
+///
+/// ```rust
+/// # use std::sync::Mutex;
+/// # use benri::sync::*;
+/// let a = Mutex::new(0);
+/// *lock_tracked!(a) += 1;
+///
+/// let stats = lock_stats();
+/// let site = stats.sites().iter().find(|s| s.line == line!() - 3).unwrap();
+/// assert!(site.count >= 1);
+/// assert!(site.holds >= 1);
+///
+/// stats.report();
+/// ```
+pub struct LockStats {
+	sites: Vec<LockSite>,
+}
+
+#[cfg(feature = "lock_stats")]
+impl LockStats {
+	#[inline]
+	/// The recorded call sites, sorted by total wait time (longest first)
+	pub fn sites(&self) -> &[LockSite] {
+		&self.sites
+	}
+
+	/// Print this report as a table
+	///
+	/// If the `log` feature flag is enabled, this uses `ok!()`, else [`std::println`].
+	pub fn report(&self) {
+		#[cfg(feature = "log")]
+		for line in self.to_string().lines() {
+			crate::ok!("{line}");
+		}
+		#[cfg(not(feature = "log"))]
+		::std::println!("{self}");
+	}
+}
+
+#[cfg(feature = "lock_stats")]
+impl fmt::Display for LockStats {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"{:<40} | {:>8} | {:>12} | {:>12} | {:>8} | {:>12} | {:>12}",
+			"site", "count", "total wait", "max wait", "holds", "total hold", "max hold",
+		)?;
+		for site in &self.sites {
+			write!(
+				f,
+				"\n{:<40} | {:>8} | {:>12.2?} | {:>12.2?} | {:>8} | {:>12.2?} | {:>12.2?}",
+				format!("{}:{}", site.file, site.line),
+				site.count,
+				site.total_wait,
+				site.max_wait,
+				site.holds,
+				site.total_hold,
+				site.max_hold,
+			)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(feature = "lock_stats")]
+/// Return the wait and hold times recorded so far
+///
+/// [`lock!`], [`lockr!`] and [`lockw!`] record wait times,
+/// [`lock_tracked!`] and friends also record hold times.
+pub fn lock_stats() -> LockStats {
+	let mut sites: Vec<LockSite> = LOCK_SITES
+		.lock()
+		.unwrap_or_else(std::sync::PoisonError::into_inner)
+		.iter()
+		.map(|site| site.snapshot())
+		.collect();
+	sites.sort_by_key(|site| std::cmp::Reverse(site.total_wait));
+	LockStats { sites }
+}
+
+#[cfg(feature = "lock_stats")]
+/// Clear all recorded lock stats
+pub fn reset_lock_stats() {
+	for site in LOCK_SITES.lock().unwrap_or_else(std::sync::PoisonError::into_inner).iter() {
+		site.reset();
+	}
+}
+
+#[cfg(feature = "lock_stats")]
+/// Warn when a [`lock_tracked!`] lock is held longer than `threshold`
+///
+/// The warning is only emitted with the `log` feature flag.
+/// [`Duration::ZERO`] (the default) disables it.
+///
+/// ```rust
+/// # use std::time::Duration;
+/// # use benri::sync::*;
+/// set_lock_hold_threshold(Duration::from_millis(100));
+/// assert!(lock_hold_threshold() == Duration::from_millis(100));
+/// ```
+pub fn set_lock_hold_threshold(threshold: Duration) {
+	LOCK_HOLD_THRESHOLD.store(threshold, Ordering::Relaxed);
+}
+
+#[cfg(feature = "lock_stats")]
+/// The current threshold set by [`set_lock_hold_threshold`]
+pub fn lock_hold_threshold() -> Duration {
+	LOCK_HOLD_THRESHOLD.load(Ordering::Relaxed)
 }
-pub use lockw;
@@ -7,24 +7,57 @@
 //use disk::{};
 //use std::{};
 //use std::sync::{Arc,Mutex,RwLock};
+use std::any::Any;
+use std::marker::PhantomData;
+use std::collections::VecDeque;
+use std::cell::{
+	Cell,
+	RefCell,
+};
+use std::sync::atomic::{
+	AtomicBool,
+	AtomicU64,
+	AtomicUsize,
+	Ordering,
+};
+use std::sync::mpsc::{
+	self,
+	Sender,
+};
+use std::sync::{
+	Arc,
+	Condvar,
+	Mutex,
+	OnceLock,
+};
+use std::thread::{
+	JoinHandle,
+	Thread,
+};
+use std::time::{
+	Duration,
+	Instant,
+};
 
 //---------------------------------------------------------------------------------------------------- Thread
 #[macro_export]
 /// Sleep the current thread for `x` seconds
 ///
+/// If a [`Clock`](crate::time::Clock) override is set, this calls [`Clock::sleep`](crate::time::Clock::sleep) instead.
+///
 /// ```rust
 /// # use benri::time::*;
 /// # use benri::thread::*;
 /// let now = now!();
 ///
 /// // This sleeps the current thread for 1 second.
-/// sleep_secs!(1);
+/// sleep!(1);
 ///
 /// assert!(secs!(now) >= 1);
 /// ```
 macro_rules! sleep {
     ($seconds:expr) => {
-		::std::thread::sleep(::std::time::Duration::from_secs($seconds))
+		$crate::time::clock_sleep(::std::time::Duration::from_secs($seconds))
     }
 }
 pub use sleep;
@@ -32,19 +65,21 @@ pub use sleep;
 #[macro_export]
 /// Sleep the current thread for `x` milliseconds
 ///
+/// If a [`Clock`](crate::time::Clock) override is set, this calls [`Clock::sleep`](crate::time::Clock::sleep) instead.
+///
 /// ```rust
 /// # use benri::time::*;
 /// # use benri::thread::*;
 /// let now = now!();
 ///
 /// // This sleeps the current thread for 1 second.
-/// sleep!(1000);
+/// sleep_millis!(1000);
 ///
 /// assert!(secs!(now) >= 1);
 /// ```
 macro_rules! sleep_millis {
     ($millis:expr) => {
-		::std::thread::sleep(::std::time::Duration::from_millis($millis))
+		$crate::time::clock_sleep(::std::time::Duration::from_millis($millis))
     }
 }
 pub use sleep_millis;
@@ -52,18 +87,20 @@ pub use sleep_millis;
 #[macro_export]
 /// Sleep the current thread for `x` microseconds
 ///
+/// If a [`Clock`](crate::time::Clock) override is set, this calls [`Clock::sleep`](crate::time::Clock::sleep) instead.
+///
 /// ```rust
 /// # use benri::*;
 /// let now = now!();
 ///
 /// // This sleeps the current thread for 1 second.
-/// sleep_nanos!(1_000_000);
+/// sleep_micros!(1_000_000);
 ///
 /// assert!(secs!(now) >= 1);
 /// ```
 macro_rules! sleep_micros {
     ($micros:expr) => {
-		::std::thread::sleep(::std::time::Duration::from_micros($micros))
+		$crate::time::clock_sleep(::std::time::Duration::from_micros($micros))
     }
 }
 pub use sleep_micros;
@@ -71,6 +108,8 @@ pub use sleep_micros;
 #[macro_export]
 /// Sleep the current thread for `x` nanoseconds
 ///
+/// If a [`Clock`](crate::time::Clock) override is set, this calls [`Clock::sleep`](crate::time::Clock::sleep) instead.
+///
 /// ```rust
 /// # use benri::*;
 /// let now = now!();
@@ -82,7 +121,7 @@ pub use sleep_micros;
 /// ```
 macro_rules! sleep_nanos {
     ($nanos:expr) => {
-		::std::thread::sleep(::std::time::Duration::from_nanos($nanos))
+		$crate::time::clock_sleep(::std::time::Duration::from_nanos($nanos))
     }
 }
 pub use sleep_nanos;
@@ -97,61 +136,181 @@ macro_rules! park {
 pub use park;
 
 #[macro_export]
-/// Get the total available amount of threads as a [`usize`]
+/// Calls [`std::thread::park_timeout`]
+///
+/// ```rust
+/// # use benri::thread::*;
+/// # use std::time::*;
+/// park_timeout!(Duration::from_millis(10));
+/// ```
+macro_rules! park_timeout {
+    ($duration:expr) => {
+		::std::thread::park_timeout($duration)
+    }
+}
+pub use park_timeout;
+
+#[macro_export]
+/// Unpark a [`std::thread::Thread`], [`std::thread::JoinHandle`] or [`Unparker`](crate::thread::Unparker)
 ///
-/// Calls [`std::thread::available_parallelism`] and converts to a [`usize`]
+/// This calls [`Unpark::unpark`](crate::thread::Unpark::unpark).
 ///
-/// If the above function returns [`Err`], `1` will be returned.
-macro_rules! threads {
-    () => {
-		match ::std::thread::available_parallelism() {
-			Ok(t)  => usize::from(t),
-			Err(e) => {
+/// ```rust
+/// # use benri::thread::*;
+/// let handle = std::thread::spawn(|| park!());
+///
+/// unpark!(handle);
+/// handle.join().unwrap();
+/// ```
+macro_rules! unpark {
+    ($handle:expr) => {
+		$crate::thread::Unpark::unpark(&$handle)
+    }
+}
+pub use unpark;
+
+/// The environment variable that overrides [`available_threads`]
+pub const THREADS_ENV: &str = "BENRI_THREADS";
+
+/// Get the total available amount of threads
+///
+/// This is what [`threads!`] and the other thread-count macros are based on.
+///
+/// If the [`THREADS_ENV`] (`BENRI_THREADS`) environment variable is set
+/// to a positive integer, that is returned instead, so containers and CI
+/// can limit parallelism. Invalid values are ignored.
+///
+/// The variable is only read (and warned about if invalid) once per process.
+///
+/// Otherwise, [`std::thread::available_parallelism`] is used, which
+/// on Linux already takes cgroup CPU quotas and affinity masks into account.
+///
+/// If that returns [`Err`], `1` will be returned.
+pub fn available_threads() -> usize {
+	if let Some(t) = threads_env() {
+		return t;
+	}
+
+	match std::thread::available_parallelism() {
+		Ok(t)  => usize::from(t),
+		Err(_) => {
+			#[cfg(feature = "log")]
+			::log::error!("std::thread::available_parallelism() failed, returning 1!");
+			1
+		},
+	}
+}
+
+// The parsed `THREADS_ENV` override, read once.
+fn threads_env() -> Option<usize> {
+	static THREADS: OnceLock<Option<usize>> = OnceLock::new();
+
+	*THREADS.get_or_init(|| {
+		let s = std::env::var(THREADS_ENV).ok()?;
+		match s.trim().parse::<usize>() {
+			Ok(t) if t > 0 => Some(t),
+			_ => {
 				#[cfg(feature = "log")]
-				::log::error!("std::thread::available_parallelism() failed, returning 1!");
-				1_usize
+				::log::warn!("invalid {THREADS_ENV}: {s:?}, ignoring");
+				None
 			},
 		}
+	})
+}
+
+/// Get `numerator / denominator` of [`available_threads`]
+///
+/// The result is rounded down, then clamped so that it is
+/// always at least `1` and at most [`available_threads`].
+///
+/// A `denominator` of `0` returns [`available_threads`].
+pub fn fraction_of_threads(numerator: usize, denominator: usize) -> usize {
+	let threads = available_threads();
+	if denominator == 0 {
+		return threads;
+	}
+
+	let t = (threads as u128 * numerator as u128 / denominator as u128) as usize;
+	t.clamp(1, threads)
+}
+
+#[macro_export]
+/// Get the total available amount of threads as a [`usize`]
+///
+/// This calls [`available_threads`](crate::thread::available_threads),
+/// which respects the `BENRI_THREADS` environment variable.
+///
+/// ```rust
+/// # use benri::thread::*;
+/// assert!(threads!() >= 1);
+/// ```
+macro_rules! threads {
+    () => {
+		$crate::thread::available_threads()
     }
 }
 pub use threads;
 
+#[macro_export]
+/// Get `numerator / denominator` of the available threads as a `usize`
+///
+/// This rounds down, and is clamped to `1..=threads!()`.
+///
+/// See [`fraction_of_threads`](crate::thread::fraction_of_threads).
+///
+/// ```rust
+/// # use benri::thread::*;
+/// assert!(threads_fraction!(1, 1) == threads!());
+/// assert!(threads_fraction!(2, 1) == threads!());
+/// assert!(threads_fraction!(0, 1) == 1);
+/// assert!(threads_fraction!(1, 3) == (threads!() / 3).max(1));
+/// ```
+macro_rules! threads_fraction {
+    ($numerator:expr, $denominator:expr) => {
+		$crate::thread::fraction_of_threads($numerator, $denominator)
+    }
+}
+pub use threads_fraction;
+
+#[macro_export]
+/// Get `n%` of the available threads as a `usize`
+///
+/// This rounds down, and is clamped to `1..=threads!()`.
+///
+/// ```rust
+/// # use benri::thread::*;
+/// assert!(threads_percent!(100) == threads!());
+/// assert!(threads_percent!(50)  == half_threads!());
+/// assert!(threads_percent!(0)   == 1);
+/// ```
+macro_rules! threads_percent {
+    ($percent:expr) => {
+		$crate::thread::fraction_of_threads($percent, 100)
+    }
+}
+pub use threads_percent;
+
 #[macro_export]
 /// Get `25%` of the available threads as a `usize`.
 ///
 /// This returns `1` on either `1|2|3` threads, else
 /// it multiplies by `0.25` and rounds down.
 macro_rules! quarter_threads {
-    () => {{
-		let threads = $crate::threads!();
-		match threads {
-			// Special cases (low thread-count).
-			1|2 => 1,
-
-			// Around 50%.
-			_ => (threads as f64 * 0.5).floor() as usize,
-		}
-    }}
+    () => {
+		$crate::thread::fraction_of_threads(1, 4)
+    }
 }
 pub use quarter_threads;
 
-
 #[macro_export]
 /// Get `50%` the available threads as a `usize`.
 ///
 /// This returns `1` on either `1|2` threads, else
 /// it multiplies by `0.5` and rounds down.
 macro_rules! half_threads {
-    () => {{
-		let threads = $crate::threads!();
-		match threads {
-			// Special cases (low thread-count).
-			1|2 => 1,
-
-			// Around 50%.
-			_ => (threads as f64 * 0.5).floor() as usize,
-		}
-    }}
+    () => {
+		$crate::thread::fraction_of_threads(1, 2)
+    }
 }
 pub use half_threads;
 
@@ -165,17 +324,1644 @@ pub use half_threads;
 ///
 /// else, it multiplies by `0.8` and rounds down.
 macro_rules! most_threads {
-    () => {{
-		let threads = $crate::threads!();
-		match threads {
-			// Special cases (low thread-count).
-			1|2 => 1,
-			3   => 2,
-			4   => 3,
+    () => {
+		$crate::thread::fraction_of_threads(4, 5)
+    }
+}
+pub use most_threads;
 
-			// Around 80%.
-			_ => (threads as f64 * 0.8).floor() as usize,
+
+//---------------------------------------------------------------------------------------------------- Spawn
+// Extract the message from a panic payload, if it is a string.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> &str {
+	if let Some(s) = payload.downcast_ref::<&'static str>() {
+		s
+	} else if let Some(s) = payload.downcast_ref::<String>() {
+		s
+	} else {
+		"<non-string panic payload>"
+	}
+}
+
+/// Spawn a named thread with [`std::thread::Builder`]
+///
+/// This is what [`spawn!`] and [`spawn_or_mass!`] call.
+///
+/// - `stack_size` sets [`std::thread::Builder::stack_size`] if [`Some`]
+/// - `or_mass` will [`mass_panic!`](crate::panic::mass_panic) if the thread panics
+///
+/// If the `log` feature flag is enabled, this logs when
+/// the thread starts and exits with `ok_debug!()`.
+///
+/// # Panics
+/// This panics if the OS fails to create the thread, like [`std::thread::spawn`].
+pub fn spawn_named<F, T>(name: &str, stack_size: Option<usize>, or_mass: bool, f: F) -> JoinHandle<T>
+where
+	F: FnOnce() -> T + Send + 'static,
+	T: Send + 'static,
+{
+	let mut builder = std::thread::Builder::new().name(name.to_string());
+	if let Some(size) = stack_size {
+		builder = builder.stack_size(size);
+	}
+
+	let name = name.to_string();
+	let result = builder.spawn(move || {
+		#[cfg(feature = "log")]
+		crate::ok_debug!("thread [{name}] ... started");
+
+		let t = if or_mass {
+			match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+				Ok(t)  => t,
+				Err(e) => crate::mass_panic!("thread [{}] panicked: {}", name, panic_message(&*e)),
+			}
+		} else {
+			f()
+		};
+
+		#[cfg(feature = "log")]
+		crate::ok_debug!("thread [{name}] ... exited");
+		t
+	});
+
+	match result {
+		Ok(handle) => handle,
+		Err(e)     => panic!("failed to spawn thread: {e}"),
+	}
+}
+
+#[macro_export]
+/// Spawn a named thread, returning its [`std::thread::JoinHandle`]
+///
+/// A stack size in bytes can be passed before the closure.
+///
+/// If the `log` feature flag is enabled, this logs when
+/// the thread starts and exits with `ok_debug!()`.
+///
+/// ```rust
+/// # use benri::thread::*;
+/// let handle = spawn!("worker", || {
+///     assert!(std::thread::current().name() == Some("worker"));
+///     123
+/// });
+/// assert!(handle.join().unwrap() == 123);
+///
+/// let handle = spawn!("big_stack", 8 * 1024 * 1024, || 456);
+/// assert!(handle.join().unwrap() == 456);
+/// ```
+macro_rules! spawn {
+	($name:expr, $f:expr) => {
+		$crate::thread::spawn_named($name, ::std::option::Option::None, false, $f)
+	};
+	($name:expr, $stack_size:expr, $f:expr) => {
+		$crate::thread::spawn_named($name, ::std::option::Option::Some($stack_size), false, $f)
+	};
+}
+pub use spawn;
+
+#[macro_export]
+/// Spawn a named thread that will [`mass_panic!`](crate::panic::mass_panic) if it panics
+///
+/// This is the same as [`spawn!`], except a panic in the spawned
+/// thread terminates _all_ threads, so a dead worker never goes unnoticed.
+///
+/// ```rust
+/// # use benri::thread::*;
+/// let handle = spawn_or_mass!("worker", || 123);
+/// assert!(handle.join().unwrap() == 123);
+/// ```
+macro_rules! spawn_or_mass {
+	($name:expr, $f:expr) => {
+		$crate::thread::spawn_named($name, ::std::option::Option::None, true, $f)
+	};
+	($name:expr, $stack_size:expr, $f:expr) => {
+		$crate::thread::spawn_named($name, ::std::option::Option::Some($stack_size), true, $f)
+	};
+}
+pub use spawn_or_mass;
+
+
+//---------------------------------------------------------------------------------------------------- ThreadPool
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+#[derive(Debug,Default)]
+struct PoolState {
+	// Jobs queued or running.
+	pending: Mutex<usize>,
+	// Notified when `pending` hits `0`.
+	idle: Condvar,
+	// Jobs that panicked.
+	panicked: AtomicUsize,
+}
+
+impl PoolState {
+	fn finish(&self) {
+		let mut pending = self.pending.lock().unwrap();
+		*pending -= 1;
+		if *pending == 0 {
+			self.idle.notify_all();
+		}
+	}
+
+	// Block until `pending` hits `0`.
+	fn wait(&self) {
+		let mut pending = self.pending.lock().unwrap();
+		while *pending != 0 {
+			pending = self.idle.wait(pending).unwrap();
 		}
-    }}
+	}
 }
-pub use most_threads;
+
+#[derive(Debug)]
+/// A fixed-size pool of worker threads
+///
+/// Jobs are sent to the workers over a [`std::sync::mpsc`] channel.
+///
+/// A panicking job does not kill its worker, see [`ThreadPool::panicked`].
+///
+/// Jobs passed to [`ThreadPool::execute`] must be `'static`,
+/// jobs that borrow from the current stack can be run with [`ThreadPool::scope`].
+///
+/// Dropping the [`ThreadPool`] is the same as [`ThreadPool::shutdown`].
+///
+/// ```rust
+/// # use benri::thread::*;
+/// # use std::sync::atomic::*;
+/// # use std::sync::Arc;
+/// let pool  = pool!(half);
+/// let count = Arc::new(AtomicUsize::new(0));
+///
+/// for _ in 0..100 {
+///     let count = Arc::clone(&count);
+///     pool.execute(move || { count.fetch_add(1, Ordering::SeqCst); });
+/// }
+///
+/// pool.join();
+/// assert!(count.load(Ordering::SeqCst) == 100);
+/// ```
+pub struct ThreadPool {
+	size: usize,
+	sender: Option<Sender<Job>>,
+	workers: Vec<JoinHandle<()>>,
+	state: Arc<PoolState>,
+}
+
+#[derive(Debug)]
+/// Handle for running borrowing jobs on a [`ThreadPool`], see [`ThreadPool::scope`]
+pub struct PoolScope<'pool, 'env> {
+	pool: &'pool ThreadPool,
+	// Jobs from this scope only.
+	state: Arc<PoolState>,
+	// `'env` must be invariant, else a job could borrow
+	// something that dies before the scope waits for it.
+	_env: PhantomData<&'env mut &'env ()>,
+}
+
+impl<'env> PoolScope<'_, 'env> {
+	/// Queue `f` to run on one of the [`ThreadPool`]'s workers
+	///
+	/// `f` may borrow anything that outlives the [`ThreadPool::scope`] call.
+	pub fn execute<F: FnOnce() + Send + 'env>(&self, f: F) {
+		*self.state.pending.lock().unwrap() += 1;
+
+		let state = Arc::clone(&self.state);
+		let job: Box<dyn FnOnce() + Send + 'env> = Box::new(move || {
+			if std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).is_err() {
+				state.panicked.fetch_add(1, Ordering::SeqCst);
+			}
+			state.finish();
+		});
+
+		// SAFETY: the job only borrows data that outlives `'env`, and
+		// `PoolScope` waits for every job to finish before it is dropped,
+		// which always happens before `ThreadPool::scope` returns, so
+		// the job can never run after anything it borrows is gone.
+		let job = unsafe { std::mem::transmute::<Box<dyn FnOnce() + Send + 'env>, Job>(job) };
+		self.pool.send(job);
+	}
+}
+
+impl Drop for PoolScope<'_, '_> {
+	fn drop(&mut self) {
+		// This also runs if the scope closure panics.
+		self.state.wait();
+	}
+}
+
+impl ThreadPool {
+	#[inline]
+	/// Create a [`ThreadPool`] with `size` workers named `benri-pool-{n}`
+	///
+	/// A `size` of `0` is treated as `1`.
+	pub fn new(size: usize) -> Self {
+		Self::with_name("benri-pool", size)
+	}
+
+	/// Create a [`ThreadPool`] with `size` workers named `{name}-{n}`
+	///
+	/// A `size` of `0` is treated as `1`.
+	pub fn with_name(name: &str, size: usize) -> Self {
+		let size = size.max(1);
+		let (sender, receiver) = mpsc::channel::<Job>();
+		let receiver = crate::arc_mut!(receiver);
+		let state = Arc::new(PoolState::default());
+
+		let workers = (0..size).map(|i| {
+			let receiver = Arc::clone(&receiver);
+			let state    = Arc::clone(&state);
+			spawn_named(&format!("{name}-{i}"), None, false, move || loop {
+				// Release the lock before running the job.
+				let job = receiver.lock().unwrap().recv();
+				let Ok(job) = job else { break };
+
+				if std::panic::catch_unwind(std::panic::AssertUnwindSafe(job)).is_err() {
+					state.panicked.fetch_add(1, Ordering::SeqCst);
+				}
+				state.finish();
+			})
+		}).collect();
+
+		Self {
+			size,
+			sender: Some(sender),
+			workers,
+			state,
+		}
+	}
+
+	#[inline]
+	/// Amount of worker threads
+	pub const fn size(&self) -> usize {
+		self.size
+	}
+
+	#[inline]
+	/// Amount of jobs that have panicked so far
+	pub fn panicked(&self) -> usize {
+		self.state.panicked.load(Ordering::SeqCst)
+	}
+
+	/// Queue `f` to run on a worker thread
+	pub fn execute<F: FnOnce() + Send + 'static>(&self, f: F) {
+		self.send(Box::new(f));
+	}
+
+	fn send(&self, job: Job) {
+		*self.state.pending.lock().unwrap() += 1;
+		if let Some(sender) = &self.sender {
+			// Workers never exit while the `Sender` exists.
+			drop(sender.send(job));
+		}
+	}
+
+	/// Block until all queued jobs have finished
+	///
+	/// The [`ThreadPool`] can still be used afterwards.
+	pub fn join(&self) {
+		self.state.wait();
+	}
+
+	/// Run jobs that borrow from the current stack on the pool's workers
+	///
+	/// Jobs [`PoolScope::execute`]'d inside `f` may borrow anything that
+	/// outlives this call, and all of them have finished when this returns.
+	///
+	/// Calling this from a job running on the same [`ThreadPool`] can
+	/// deadlock if every worker ends up waiting on a scope.
+	///
+	/// # Panics
+	/// This panics if any scoped job panicked, like [`std::thread::scope`].
+	///
+	/// ```rust
+	/// # use benri::thread::*;
+	/// # use std::sync::Mutex;
+	/// let pool = ThreadPool::new(4);
+	/// let data = vec![1, 2, 3, 4];
+	/// let sum  = Mutex::new(0);
+	///
+	/// pool.scope(|s| {
+	///     for i in &data {
+	///         let sum = &sum;
+	///         s.execute(move || *sum.lock().unwrap() += i);
+	///     }
+	/// });
+	///
+	/// assert!(*sum.lock().unwrap() == 10);
+	///
+	/// // The pool can still be used afterwards.
+	/// let mut v = vec![0; 8];
+	/// pool.scope(|s| {
+	///     for (i, x) in v.iter_mut().enumerate() {
+	///         s.execute(move || *x = i * 2);
+	///     }
+	/// });
+	/// assert!(v == [0, 2, 4, 6, 8, 10, 12, 14]);
+	/// ```
+	///
+	/// Jobs can't borrow anything created inside the scope:
+	/// ```rust,compile_fail
+	/// # use benri::thread::*;
+	/// let pool = ThreadPool::new(1);
+	/// pool.scope(|s| {
+	///     let local = 5;
+	///     s.execute(|| println!("{local}"));
+	/// });
+	/// ```
+	pub fn scope<'pool, 'env, F, T>(&'pool self, f: F) -> T
+	where
+		F: FnOnce(&PoolScope<'pool, 'env>) -> T,
+	{
+		let scope = PoolScope {
+			pool: self,
+			state: Arc::new(PoolState::default()),
+			_env: PhantomData,
+		};
+
+		let t = f(&scope);
+		scope.state.wait();
+
+		let panicked = scope.state.panicked.load(Ordering::SeqCst);
+		assert!(panicked == 0, "{panicked} scoped ThreadPool job(s) panicked");
+		t
+	}
+
+	/// Wait for all queued jobs to finish, then stop and join the worker threads
+	pub fn shutdown(self) {
+		drop(self);
+	}
+}
+
+impl Drop for ThreadPool {
+	fn drop(&mut self) {
+		// Disconnecting the channel stops the workers once it is drained.
+		drop(self.sender.take());
+		for worker in self.workers.drain(..) {
+			drop(worker.join());
+		}
+	}
+}
+
+#[macro_export]
+/// Create a [`ThreadPool`](crate::thread::ThreadPool) sized by a thread-count policy
+///
+/// | Input        | Size                   |
+/// |--------------|------------------------|
+/// | `all`        | [`threads!`]           |
+/// | `most`       | [`most_threads!`]      |
+/// | `half`       | [`half_threads!`]      |
+/// | `quarter`    | [`quarter_threads!`]   |
+/// | Any `usize`  | That amount            |
+///
+/// ```rust
+/// # use benri::thread::*;
+/// let pool = pool!(most);
+/// assert!(pool.size() == most_threads!());
+///
+/// let pool = pool!(2);
+/// assert!(pool.size() == 2);
+/// ```
+macro_rules! pool {
+	(all) => {
+		$crate::thread::ThreadPool::new($crate::threads!())
+	};
+	(most) => {
+		$crate::thread::ThreadPool::new($crate::most_threads!())
+	};
+	(half) => {
+		$crate::thread::ThreadPool::new($crate::half_threads!())
+	};
+	(quarter) => {
+		$crate::thread::ThreadPool::new($crate::quarter_threads!())
+	};
+	($size:expr) => {
+		$crate::thread::ThreadPool::new($size)
+	};
+}
+pub use pool;
+
+
+//---------------------------------------------------------------------------------------------------- Parallel iteration
+// Split `items` into at most `threads` ordered chunks, and
+// run `f` on each chunk in a scoped thread, in order.
+//
+// A panic in any thread is propagated.
+fn parallel<I, T, U, F>(items: I, threads: usize, f: F) -> Vec<U>
+where
+	I: IntoIterator<Item = T>,
+	T: Send,
+	U: Send,
+	F: Fn(Vec<T>) -> U + Sync,
+{
+	let items: Vec<T> = items.into_iter().collect();
+	if items.is_empty() {
+		return Vec::new();
+	}
+
+	let threads    = threads.clamp(1, items.len());
+	let chunk_size = items.len().div_ceil(threads);
+
+	let mut iter = items.into_iter();
+	let chunks: Vec<Vec<T>> = (0..threads)
+		.map(|_| iter.by_ref().take(chunk_size).collect())
+		.filter(|c: &Vec<T>| !c.is_empty())
+		.collect();
+
+	std::thread::scope(|s| {
+		let f = &f;
+		let handles: Vec<_> = chunks
+			.into_iter()
+			.map(|chunk| s.spawn(move || f(chunk)))
+			.collect();
+
+		handles
+			.into_iter()
+			.map(|h| match h.join() {
+				Ok(u)  => u,
+				Err(e) => std::panic::resume_unwind(e),
+			})
+			.collect()
+	})
+}
+
+/// Map `f` over `items` across `threads` scoped threads, preserving order
+///
+/// This is what [`par_map!`] calls.
+///
+/// `threads` is clamped to `1..=items.len()`.
+///
+/// # Panics
+/// If `f` panics, the panic is propagated to the caller.
+pub fn parallel_map<I, T, U, F>(items: I, threads: usize, f: F) -> Vec<U>
+where
+	I: IntoIterator<Item = T>,
+	T: Send,
+	U: Send,
+	F: Fn(T) -> U + Sync,
+{
+	parallel(items, threads, |chunk| chunk.into_iter().map(&f).collect::<Vec<U>>())
+		.into_iter()
+		.flatten()
+		.collect()
+}
+
+/// Call `f` on each of `items` across `threads` scoped threads
+///
+/// This is what [`par_for_each!`] calls.
+///
+/// `threads` is clamped to `1..=items.len()`.
+///
+/// # Panics
+/// If `f` panics, the panic is propagated to the caller.
+pub fn parallel_for_each<I, T, F>(items: I, threads: usize, f: F)
+where
+	I: IntoIterator<Item = T>,
+	T: Send,
+	F: Fn(T) + Sync,
+{
+	parallel(items, threads, |chunk| chunk.into_iter().for_each(&f));
+}
+
+/// Map `f` over `chunk_size` chunks of `slice` across `threads` scoped threads, preserving order
+///
+/// This is what [`par_chunks!`] calls.
+///
+/// # Panics
+/// This panics if `chunk_size` is `0`.
+///
+/// If `f` panics, the panic is propagated to the caller.
+pub fn parallel_chunks<T, U, F>(slice: &[T], chunk_size: usize, threads: usize, f: F) -> Vec<U>
+where
+	T: Sync,
+	U: Send,
+	F: Fn(&[T]) -> U + Sync,
+{
+	parallel_map(slice.chunks(chunk_size), threads, f)
+}
+
+#[macro_export]
+/// Map a closure over items in parallel, preserving order
+///
+/// The items can be anything that implements [`IntoIterator`], e.g: a `Vec`, `&[T]`, or a range.
+///
+/// The thread count can be passed before the closure, either as a
+/// `usize`, or as `all`, `most`, `half` or `quarter`, which use the
+/// matching thread-count macro. The default is [`threads!`].
+///
+/// This calls [`parallel_map`](crate::thread::parallel_map).
+///
+/// ```rust
+/// # use benri::thread::*;
+/// let v: Vec<u64> = (0..100).collect();
+///
+/// let doubled = par_map!(&v, |x| x * 2);
+/// assert!(doubled == (0..100).map(|x| x * 2).collect::<Vec<u64>>());
+///
+/// let squared = par_map!(0..10_u64, half, |x| x * x);
+/// assert!(squared[9] == 81);
+///
+/// let strings = par_map!(v, 3, |x| x.to_string());
+/// assert!(strings[99] == "99");
+/// ```
+macro_rules! par_map {
+	($items:expr, $f:expr) => {
+		$crate::thread::parallel_map($items, $crate::threads!(), $f)
+	};
+	($items:expr, all, $f:expr) => {
+		$crate::thread::parallel_map($items, $crate::threads!(), $f)
+	};
+	($items:expr, most, $f:expr) => {
+		$crate::thread::parallel_map($items, $crate::most_threads!(), $f)
+	};
+	($items:expr, half, $f:expr) => {
+		$crate::thread::parallel_map($items, $crate::half_threads!(), $f)
+	};
+	($items:expr, quarter, $f:expr) => {
+		$crate::thread::parallel_map($items, $crate::quarter_threads!(), $f)
+	};
+	($items:expr, $threads:expr, $f:expr) => {
+		$crate::thread::parallel_map($items, $threads, $f)
+	};
+}
+pub use par_map;
+
+#[macro_export]
+/// Call a closure on items in parallel
+///
+/// This takes the same inputs as [`par_map!`], and calls
+/// [`parallel_for_each`](crate::thread::parallel_for_each).
+///
+/// ```rust
+/// # use benri::thread::*;
+/// # use std::sync::atomic::*;
+/// let sum = AtomicU64::new(0);
+///
+/// par_for_each!(1..=100_u64, most, |x| { sum.fetch_add(x, Ordering::SeqCst); });
+/// assert!(sum.load(Ordering::SeqCst) == 5050);
+/// ```
+macro_rules! par_for_each {
+	($items:expr, $f:expr) => {
+		$crate::thread::parallel_for_each($items, $crate::threads!(), $f)
+	};
+	($items:expr, all, $f:expr) => {
+		$crate::thread::parallel_for_each($items, $crate::threads!(), $f)
+	};
+	($items:expr, most, $f:expr) => {
+		$crate::thread::parallel_for_each($items, $crate::most_threads!(), $f)
+	};
+	($items:expr, half, $f:expr) => {
+		$crate::thread::parallel_for_each($items, $crate::half_threads!(), $f)
+	};
+	($items:expr, quarter, $f:expr) => {
+		$crate::thread::parallel_for_each($items, $crate::quarter_threads!(), $f)
+	};
+	($items:expr, $threads:expr, $f:expr) => {
+		$crate::thread::parallel_for_each($items, $threads, $f)
+	};
+}
+pub use par_for_each;
+
+#[macro_export]
+/// Map a closure over fixed-size chunks of a slice in parallel, preserving order
+///
+/// The chunk size comes after the slice, then the optional
+/// thread count like [`par_map!`].
+///
+/// This calls [`parallel_chunks`](crate::thread::parallel_chunks).
+///
+/// ```rust
+/// # use benri::thread::*;
+/// let v: Vec<u64> = (1..=10).collect();
+///
+/// let sums = par_chunks!(&v, 3, |chunk| chunk.iter().sum::<u64>());
+/// assert!(sums == [6, 15, 24, 10]);
+///
+/// let sums = par_chunks!(&v, 5, 2, |chunk| chunk.iter().sum::<u64>());
+/// assert!(sums == [15, 40]);
+/// ```
+macro_rules! par_chunks {
+	($slice:expr, $chunk_size:expr, $f:expr) => {
+		$crate::thread::parallel_chunks($slice, $chunk_size, $crate::threads!(), $f)
+	};
+	($slice:expr, $chunk_size:expr, all, $f:expr) => {
+		$crate::thread::parallel_chunks($slice, $chunk_size, $crate::threads!(), $f)
+	};
+	($slice:expr, $chunk_size:expr, most, $f:expr) => {
+		$crate::thread::parallel_chunks($slice, $chunk_size, $crate::most_threads!(), $f)
+	};
+	($slice:expr, $chunk_size:expr, half, $f:expr) => {
+		$crate::thread::parallel_chunks($slice, $chunk_size, $crate::half_threads!(), $f)
+	};
+	($slice:expr, $chunk_size:expr, quarter, $f:expr) => {
+		$crate::thread::parallel_chunks($slice, $chunk_size, $crate::quarter_threads!(), $f)
+	};
+	($slice:expr, $chunk_size:expr, $threads:expr, $f:expr) => {
+		$crate::thread::parallel_chunks($slice, $chunk_size, $threads, $f)
+	};
+}
+pub use par_chunks;
+
+
+//---------------------------------------------------------------------------------------------------- Park
+/// Types that can wake up a parked thread, see [`unpark!`]
+pub trait Unpark {
+	/// Wake up the parked thread
+	fn unpark(&self);
+}
+
+impl Unpark for Thread {
+	#[inline]
+	fn unpark(&self) {
+		Thread::unpark(self);
+	}
+}
+
+impl<T> Unpark for JoinHandle<T> {
+	#[inline]
+	fn unpark(&self) {
+		self.thread().unpark();
+	}
+}
+
+impl Unpark for Unparker {
+	#[inline]
+	fn unpark(&self) {
+		Unparker::unpark(self);
+	}
+}
+
+#[derive(Debug)]
+struct ParkInner {
+	notified: AtomicBool,
+	// The thread currently using the `Parker`.
+	thread: Mutex<Thread>,
+}
+
+#[derive(Debug)]
+/// The parking half of a [`Parker`]/[`Unparker`] pair
+///
+/// Unlike plain [`std::thread::park`], a notification from the
+/// [`Unparker`] is never lost: if [`Unparker::unpark`] is called
+/// before [`Parker::park`], the next park returns immediately.
+///
+/// The [`Parker`] can be moved to another thread, whichever thread
+/// calls [`Parker::park`] is the one that gets woken up.
+///
+/// ```rust
+/// # use benri::thread::*;
+/// let parker   = Parker::new();
+/// let unparker = parker.unparker();
+///
+/// let handle = std::thread::spawn(move || {
+///     parker.park();
+/// });
+///
+/// unparker.unpark();
+/// handle.join().unwrap();
+/// ```
+pub struct Parker {
+	inner: Arc<ParkInner>,
+}
+
+#[derive(Clone,Debug)]
+/// The waking half of a [`Parker`]/[`Unparker`] pair
+///
+/// This is cheaply [`Clone`]-able.
+pub struct Unparker {
+	inner: Arc<ParkInner>,
+}
+
+impl Parker {
+	/// Create a new [`Parker`]
+	pub fn new() -> Self {
+		Self {
+			inner: Arc::new(ParkInner {
+				notified: AtomicBool::new(false),
+				thread: Mutex::new(std::thread::current()),
+			}),
+		}
+	}
+
+	/// Create an [`Unparker`] for this [`Parker`]
+	pub fn unparker(&self) -> Unparker {
+		Unparker { inner: Arc::clone(&self.inner) }
+	}
+
+	// Register the current thread as the one to wake.
+	fn register(&self) {
+		let current = std::thread::current();
+		let mut thread = self.inner.thread.lock().unwrap();
+		if thread.id() != current.id() {
+			*thread = current;
+		}
+	}
+
+	#[inline]
+	// Consume a notification, if there is one.
+	fn take(&self) -> bool {
+		self.inner.notified.swap(false, Ordering::SeqCst)
+	}
+
+	/// Block the current thread until [`Unparker::unpark`] is called
+	pub fn park(&self) {
+		self.register();
+		while !self.take() {
+			std::thread::park();
+		}
+	}
+
+	/// Block the current thread until [`Unparker::unpark`] is called or `timeout` passes
+	///
+	/// Returns `true` if unparked, `false` on timeout.
+	pub fn park_timeout(&self, timeout: Duration) -> bool {
+		self.register();
+		let deadline = Instant::now() + timeout;
+		loop {
+			if self.take() {
+				return true;
+			}
+			let remaining = deadline.saturating_duration_since(Instant::now());
+			if remaining.is_zero() {
+				return false;
+			}
+			std::thread::park_timeout(remaining);
+		}
+	}
+
+	/// Park until `condition` returns `true`
+	///
+	/// `condition` is checked first, then again after every
+	/// wake-up, so spurious wake-ups are handled.
+	pub fn wait_until<F: FnMut() -> bool>(&self, mut condition: F) {
+		while !condition() {
+			self.park();
+		}
+	}
+
+	/// Park until `condition` returns `true` or `timeout` passes
+	///
+	/// Returns the last result of `condition`.
+	pub fn wait_until_timeout<F: FnMut() -> bool>(&self, timeout: Duration, mut condition: F) -> bool {
+		let deadline = Instant::now() + timeout;
+		loop {
+			if condition() {
+				return true;
+			}
+			let remaining = deadline.saturating_duration_since(Instant::now());
+			if remaining.is_zero() || !self.park_timeout(remaining) {
+				return condition();
+			}
+		}
+	}
+}
+
+impl Default for Parker {
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Unparker {
+	/// Wake up the thread parked on the matching [`Parker`]
+	pub fn unpark(&self) {
+		self.inner.notified.store(true, Ordering::SeqCst);
+		self.inner.thread.lock().unwrap().unpark();
+	}
+}
+
+#[macro_export]
+/// Park on a [`Parker`](crate::thread::Parker) until a condition is `true`
+///
+/// This is safe against spurious wake-ups, the condition is re-checked after every wake-up.
+///
+/// A [`std::time::Duration`] timeout can be passed before the condition,
+/// in which case this returns the last result of the condition as a `bool`.
+///
+/// ```rust
+/// # use benri::thread::*;
+/// # use std::sync::atomic::*;
+/// # use std::sync::Arc;
+/// # use std::time::Duration;
+/// let parker   = Parker::new();
+/// let unparker = parker.unparker();
+/// let ready    = Arc::new(AtomicBool::new(false));
+///
+/// let r = Arc::clone(&ready);
+/// std::thread::spawn(move || {
+///     r.store(true, Ordering::SeqCst);
+///     unpark!(unparker);
+/// });
+///
+/// wait_until!(parker, || ready.load(Ordering::SeqCst));
+///
+/// // Times out.
+/// assert!(!wait_until!(parker, Duration::from_millis(10), || false));
+/// ```
+macro_rules! wait_until {
+    ($parker:expr, $condition:expr) => {
+		$parker.wait_until($condition)
+    };
+    ($parker:expr, $timeout:expr, $condition:expr) => {
+		$parker.wait_until_timeout($timeout, $condition)
+    };
+}
+pub use wait_until;
+
+
+//---------------------------------------------------------------------------------------------------- Thread local
+/// Uniform access to [`Cell`] and [`RefCell`], used by the `tls_*!()` macros
+pub trait TlsCell {
+	/// The type inside the cell
+	type Value;
+
+	/// Get a copy of the inner value
+	fn tls_get(&self) -> Self::Value
+	where
+		Self::Value: Clone;
+
+	/// Replace the inner value
+	fn tls_set(&self, value: Self::Value);
+
+	/// Call `f` with mutable access to the inner value
+	fn tls_with<R, F: FnOnce(&mut Self::Value) -> R>(&self, f: F) -> R;
+}
+
+impl<T: Copy> TlsCell for Cell<T> {
+	type Value = T;
+
+	#[inline]
+	fn tls_get(&self) -> T {
+		self.get()
+	}
+
+	#[inline]
+	fn tls_set(&self, value: T) {
+		self.set(value);
+	}
+
+	#[inline]
+	fn tls_with<R, F: FnOnce(&mut T) -> R>(&self, f: F) -> R {
+		let mut value = self.get();
+		let r = f(&mut value);
+		self.set(value);
+		r
+	}
+}
+
+impl<T> TlsCell for RefCell<T> {
+	type Value = T;
+
+	#[inline]
+	fn tls_get(&self) -> T
+	where
+		T: Clone,
+	{
+		self.borrow().clone()
+	}
+
+	#[inline]
+	fn tls_set(&self, value: T) {
+		*self.borrow_mut() = value;
+	}
+
+	#[inline]
+	fn tls_with<R, F: FnOnce(&mut T) -> R>(&self, f: F) -> R {
+		f(&mut self.borrow_mut())
+	}
+}
+
+#[macro_export]
+/// Declare lazily initialized thread-local values
+///
+/// This wraps [`std::thread_local!`], the initializer
+/// runs the first time each thread accesses the value.
+///
+/// The type is wrapped automatically:
+/// - `Cell<T>` is declared as a [`std::cell::Cell`] (`T` must be [`Copy`])
+/// - `RefCell<T>` is declared as a [`std::cell::RefCell`]
+/// - Any other `T` is declared as a [`std::cell::RefCell`]
+///
+/// The values can then be accessed with [`tls_get!`], [`tls_set!`] and [`tls_with!`].
+///
+/// ```rust
+/// # use benri::thread::*;
+/// tls! {
+///     static COUNT: Cell<u32> = 0;
+///     static NAMES: Vec<String> = Vec::new();
+/// }
+///
+/// tls_set!(COUNT, 5);
+/// tls_with!(COUNT, |c| *c += 1);
+/// assert!(tls_get!(COUNT) == 6);
+///
+/// tls_with!(NAMES, |v| v.push("hello".into()));
+/// assert!(tls_get!(NAMES) == ["hello"]);
+///
+/// // Other threads get their own values.
+/// std::thread::spawn(|| {
+///     assert!(tls_get!(COUNT) == 0);
+///     assert!(tls_get!(NAMES).is_empty());
+/// }).join().unwrap();
+/// ```
+macro_rules! tls {
+	() => {};
+
+	($(#[$attr:meta])* $vis:vis static $name:ident: Cell<$t:ty> = $init:expr $(; $($rest:tt)*)?) => {
+		::std::thread_local! {
+			$(#[$attr])* $vis static $name: ::std::cell::Cell<$t> = ::std::cell::Cell::new($init);
+		}
+		$($crate::tls!($($rest)*);)?
+	};
+
+	($(#[$attr:meta])* $vis:vis static $name:ident: RefCell<$t:ty> = $init:expr $(; $($rest:tt)*)?) => {
+		::std::thread_local! {
+			$(#[$attr])* $vis static $name: ::std::cell::RefCell<$t> = ::std::cell::RefCell::new($init);
+		}
+		$($crate::tls!($($rest)*);)?
+	};
+
+	($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = $init:expr $(; $($rest:tt)*)?) => {
+		::std::thread_local! {
+			$(#[$attr])* $vis static $name: ::std::cell::RefCell<$t> = ::std::cell::RefCell::new($init);
+		}
+		$($crate::tls!($($rest)*);)?
+	};
+}
+pub use tls;
+
+#[macro_export]
+/// Get a copy of a thread-local value declared with [`tls!`]
+///
+/// [`std::cell::RefCell`] values are [`Clone`]'d.
+macro_rules! tls_get {
+	($key:expr) => {
+		$key.with(|c| $crate::thread::TlsCell::tls_get(c))
+	}
+}
+pub use tls_get;
+
+#[macro_export]
+/// Replace a thread-local value declared with [`tls!`]
+macro_rules! tls_set {
+	($key:expr, $value:expr) => {
+		$key.with(|c| $crate::thread::TlsCell::tls_set(c, $value))
+	}
+}
+pub use tls_set;
+
+#[macro_export]
+/// Call a closure with mutable access to a thread-local value declared with [`tls!`]
+///
+/// This returns the closure's return value.
+macro_rules! tls_with {
+	($key:expr, $f:expr) => {
+		$key.with(|c| $crate::thread::TlsCell::tls_with(c, $f))
+	}
+}
+pub use tls_with;
+
+
+//---------------------------------------------------------------------------------------------------- Supervisor
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+/// What a [`Supervisor`] does when a worker panics
+pub enum RestartPolicy {
+	/// Never restart the worker
+	Never,
+	/// Always restart the worker
+	Always,
+	/// Restart the worker, unless it has already been restarted `max` times within `window`
+	UpTo {
+		/// Maximum restarts within `window`
+		max: u32,
+		/// The sliding window restarts are counted in
+		window: Duration,
+	},
+	/// [`mass_panic!`](crate::panic::mass_panic)
+	MassPanic,
+}
+
+#[derive(Clone,Debug,PartialEq,Eq,Hash)]
+/// Something that happened to a [`Supervisor`] worker, returned by [`Supervisor::check`]
+pub enum SupervisorEvent {
+	/// The worker returned normally and will not be restarted
+	Exited(String),
+	/// The worker panicked with this message
+	Panicked(String, String),
+	/// The worker was restarted after panicking
+	Restarted(String),
+	/// The worker hit its [`RestartPolicy`] limit and will not be restarted
+	GaveUp(String),
+}
+
+struct Worker {
+	name: String,
+	policy: RestartPolicy,
+	f: Arc<dyn Fn() + Send + Sync + 'static>,
+	// Only `None` while being joined.
+	handle: Option<JoinHandle<()>>,
+	restarts: VecDeque<Instant>,
+}
+
+impl Worker {
+	fn spawn(name: &str, f: &Arc<dyn Fn() + Send + Sync + 'static>) -> JoinHandle<()> {
+		let f = Arc::clone(f);
+		spawn_named(name, None, false, move || f())
+	}
+}
+
+impl std::fmt::Debug for Worker {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Worker")
+			.field("name", &self.name)
+			.field("policy", &self.policy)
+			.field("restarts", &self.restarts)
+			.finish_non_exhaustive()
+	}
+}
+
+#[derive(Debug,Default)]
+/// Owns named worker threads and restarts them when they panic
+///
+/// Workers are re-runnable closures, each with a [`RestartPolicy`].
+///
+/// Panics are detected with [`JoinHandle::join`] in [`Supervisor::check`]
+/// (non-blocking) or [`Supervisor::run`] (blocking).
+///
+/// If the `log` feature flag is enabled, every [`SupervisorEvent`] is logged.
+///
+/// Dropping a [`Supervisor`] detaches its workers, they keep running
+/// but are no longer restarted. Use [`Supervisor::shutdown`] to wait for them.
+///
+/// ```rust
+/// # use benri::thread::*;
+/// # use std::sync::atomic::*;
+/// # use std::sync::Arc;
+/// let runs = Arc::new(AtomicUsize::new(0));
+/// let mut supervisor = Supervisor::new();
+///
+/// let r = Arc::clone(&runs);
+/// supervisor.spawn("flaky", RestartPolicy::Always, move || {
+///     // Panic twice, then exit normally.
+///     if r.fetch_add(1, Ordering::SeqCst) < 2 {
+///         panic!("oops");
+///     }
+/// });
+///
+/// let events = supervisor.run();
+/// assert!(runs.load(Ordering::SeqCst) == 3);
+/// assert!(events.last() == Some(&SupervisorEvent::Exited("flaky".into())));
+///
+/// supervisor.spawn("fragile", RestartPolicy::Never, || panic!("oops"));
+/// let events = supervisor.run();
+/// assert!(events.last() == Some(&SupervisorEvent::GaveUp("fragile".into())));
+/// ```
+pub struct Supervisor {
+	workers: Vec<Worker>,
+}
+
+impl Supervisor {
+	#[inline]
+	/// Create a [`Supervisor`] with no workers
+	pub fn new() -> Self {
+		Self { workers: Vec::new() }
+	}
+
+	/// Spawn a named worker thread running `f` with a [`RestartPolicy`]
+	pub fn spawn<F>(&mut self, name: &str, policy: RestartPolicy, f: F)
+	where
+		F: Fn() + Send + Sync + 'static,
+	{
+		let f: Arc<dyn Fn() + Send + Sync + 'static> = Arc::new(f);
+		let handle = Worker::spawn(name, &f);
+		self.workers.push(Worker {
+			name: name.to_string(),
+			policy,
+			f,
+			handle: Some(handle),
+			restarts: VecDeque::new(),
+		});
+	}
+
+	#[inline]
+	/// Amount of workers currently supervised
+	pub fn len(&self) -> usize {
+		self.workers.len()
+	}
+
+	#[inline]
+	/// Returns `true` if there are no workers left
+	pub fn is_empty(&self) -> bool {
+		self.workers.is_empty()
+	}
+
+	/// Check all workers once without blocking, restarting any that panicked
+	///
+	/// Workers that exited or will not be restarted are removed.
+	pub fn check(&mut self) -> Vec<SupervisorEvent> {
+		self.check_workers(true)
+	}
+
+	// `check()`, but `restart == false` gives up on every panicked worker.
+	fn check_workers(&mut self, restart: bool) -> Vec<SupervisorEvent> {
+		let mut events = Vec::new();
+		let mut i = 0;
+
+		while i < self.workers.len() {
+			let worker = &mut self.workers[i];
+			let handle = match worker.handle.take() {
+				Some(h) if h.is_finished() => h,
+				h => {
+					worker.handle = h;
+					i += 1;
+					continue;
+				},
+			};
+			let name = worker.name.clone();
+
+			let payload = match handle.join() {
+				Ok(()) => {
+					events.push(SupervisorEvent::Exited(name));
+					self.workers.swap_remove(i);
+					continue;
+				},
+				Err(payload) => payload,
+			};
+			events.push(SupervisorEvent::Panicked(name.clone(), panic_message(&*payload).to_string()));
+
+			// The policy is always checked, so `MassPanic` still applies.
+			let restart = match worker.policy {
+				RestartPolicy::Never  => false,
+				RestartPolicy::Always => true,
+				RestartPolicy::UpTo { max, window } => {
+					let now = crate::now!();
+					while worker.restarts.front().is_some_and(|t| now.saturating_duration_since(*t) > window) {
+						worker.restarts.pop_front();
+					}
+					worker.restarts.len() < max as usize
+				},
+				RestartPolicy::MassPanic => {
+					crate::mass_panic!("supervised thread [{}] panicked: {}", name, panic_message(&*payload));
+				},
+			} && restart;
+
+			if restart {
+				worker.restarts.push_back(crate::now!());
+				worker.handle = Some(Worker::spawn(&name, &worker.f));
+				events.push(SupervisorEvent::Restarted(name));
+				i += 1;
+			} else {
+				events.push(SupervisorEvent::GaveUp(name));
+				self.workers.swap_remove(i);
+			}
+		}
+
+		#[cfg(feature = "log")]
+		for event in &events {
+			match event {
+				SupervisorEvent::Exited(n)      => crate::ok_debug!("supervised thread [{n}] ... exited"),
+				SupervisorEvent::Panicked(n, m) => crate::fail!("supervised thread [{n}] ... panicked: {m}"),
+				SupervisorEvent::Restarted(n)   => crate::skip_warn!("supervised thread [{n}] ... restarted"),
+				SupervisorEvent::GaveUp(n)      => crate::fail!("supervised thread [{n}] ... not restarting"),
+			}
+		}
+
+		events
+	}
+
+	/// Block, checking workers every `10` milliseconds, until none are left
+	///
+	/// This returns all the [`SupervisorEvent`]'s that happened.
+	pub fn run(&mut self) -> Vec<SupervisorEvent> {
+		self.wait(true)
+	}
+
+	/// Stop restarting workers, then block until all of them have exited
+	///
+	/// Workers are not interrupted, so this only returns once every
+	/// worker closure returns or panics. Panicked workers are reported as
+	/// [`SupervisorEvent::Panicked`] followed by [`SupervisorEvent::GaveUp`],
+	/// except with [`RestartPolicy::MassPanic`], which still applies.
+	///
+	/// ```rust
+	/// # use benri::thread::*;
+	/// let mut supervisor = Supervisor::new();
+	/// supervisor.spawn("flaky", RestartPolicy::Always, || panic!("oops"));
+	///
+	/// let events = supervisor.shutdown();
+	/// assert!(events.last() == Some(&SupervisorEvent::GaveUp("flaky".into())));
+	/// ```
+	pub fn shutdown(mut self) -> Vec<SupervisorEvent> {
+		self.wait(false)
+	}
+
+	fn wait(&mut self, restart: bool) -> Vec<SupervisorEvent> {
+		let mut events = Vec::new();
+		while !self.is_empty() {
+			events.append(&mut self.check_workers(restart));
+			if !self.is_empty() {
+				// This is real polling, so it ignores `Clock` overrides.
+				std::thread::sleep(Duration::from_millis(10));
+			}
+		}
+		events
+	}
+}
+
+
+//---------------------------------------------------------------------------------------------------- Introspection
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+thread_local! {
+	static ID: u64 = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+}
+
+#[inline]
+/// A numeric id for the current thread
+///
+/// Ids are assigned in order starting from `0` the first time each
+/// thread calls this, and never change or get reused within a process.
+///
+/// Note that this is unrelated to [`std::thread::ThreadId`] and OS thread ids.
+pub fn current_id() -> u64 {
+	ID.with(|id| *id)
+}
+
+#[macro_export]
+/// Get the current thread's name as a [`String`]
+///
+/// If the thread has no name, its [`std::thread::ThreadId`] is used, e.g: `ThreadId(2)`.
+///
+/// ```rust
+/// # use benri::thread::*;
+/// std::thread::Builder::new().name("worker".into()).spawn(|| {
+///     assert!(thread_name!() == "worker");
+/// }).unwrap().join().unwrap();
+///
+/// std::thread::spawn(|| {
+///     assert!(thread_name!().starts_with("ThreadId("));
+/// }).join().unwrap();
+/// ```
+macro_rules! thread_name {
+	() => {{
+		let thread = ::std::thread::current();
+		match thread.name() {
+			::std::option::Option::Some(name) => ::std::string::ToString::to_string(name),
+			::std::option::Option::None       => ::std::format!("{:?}", thread.id()),
+		}
+	}}
+}
+pub use thread_name;
+
+#[macro_export]
+/// Get a stable numeric id for the current thread as a [`u64`]
+///
+/// See [`current_id`](crate::thread::current_id).
+///
+/// ```rust
+/// # use benri::thread::*;
+/// let id = thread_id!();
+/// assert!(id == thread_id!());
+///
+/// let other = std::thread::spawn(|| thread_id!()).join().unwrap();
+/// assert!(id != other);
+/// ```
+macro_rules! thread_id {
+	() => {
+		$crate::thread::current_id()
+	}
+}
+pub use thread_id;
+
+#[macro_export]
+/// Returns `true` if called from the main thread
+///
+/// This checks if the current thread's name is `main`, which is what the
+/// standard library names the main thread, so this will be wrong if
+/// another thread is manually named `main`.
+///
+/// ```rust
+/// # use benri::thread::*;
+/// let other = std::thread::spawn(|| is_main_thread!()).join().unwrap();
+/// assert!(!other);
+/// ```
+macro_rules! is_main_thread {
+	() => {
+		::std::thread::current().name() == ::std::option::Option::Some("main")
+	}
+}
+pub use is_main_thread;
+
+
+//---------------------------------------------------------------------------------------------------- Affinity/Priority
+/// Pin the current thread to the given CPU cores
+///
+/// This calls `sched_setaffinity()` on Linux, and does nothing on other platforms.
+///
+/// # Errors
+/// Returns an error if `cores` is empty, a core is out of range,
+/// or the OS rejects the affinity mask.
+pub fn set_affinity(cores: &[usize]) -> std::io::Result<()> {
+	if cores.is_empty() {
+		return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "no cores given"));
+	}
+
+	#[cfg(target_os = "linux")]
+	{
+		if cores.iter().any(|c| *c >= libc::CPU_SETSIZE as usize) {
+			return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "core out of range"));
+		}
+
+		// SAFETY: `cpu_set_t` is plain data that is valid when zeroed,
+		// and all cores were checked to be within `CPU_SETSIZE`.
+		let result = unsafe {
+			let mut set: libc::cpu_set_t = std::mem::zeroed();
+			for core in cores {
+				libc::CPU_SET(*core, &mut set);
+			}
+			// `0` means the calling thread.
+			libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set)
+		};
+
+		if result != 0 {
+			return Err(std::io::Error::last_os_error());
+		}
+	}
+
+	Ok(())
+}
+
+/// Get the CPU cores the current thread is allowed to run on
+///
+/// This calls `sched_getaffinity()` on Linux. On other
+/// platforms, every core up to [`available_threads`] is returned.
+///
+/// # Errors
+/// Returns an error if the OS call fails.
+pub fn affinity() -> std::io::Result<Vec<usize>> {
+	#[cfg(target_os = "linux")]
+	{
+		// SAFETY: `cpu_set_t` is plain data that is valid when zeroed,
+		// and `CPU_ISSET` is only called within `CPU_SETSIZE`.
+		unsafe {
+			let mut set: libc::cpu_set_t = std::mem::zeroed();
+			// `0` means the calling thread.
+			if libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+				return Err(std::io::Error::last_os_error());
+			}
+			Ok((0..libc::CPU_SETSIZE as usize).filter(|c| libc::CPU_ISSET(*c, &set)).collect())
+		}
+	}
+
+	#[cfg(not(target_os = "linux"))]
+	Ok((0..available_threads()).collect())
+}
+
+/// Set the nice value (`-20..=19`, lower is higher priority) of the current thread
+///
+/// This calls `setpriority()` on Linux, and does nothing on other platforms.
+///
+/// Lowering the nice value usually requires elevated privileges.
+///
+/// # Errors
+/// Returns an error if the OS rejects the new priority.
+#[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+pub fn set_priority(nice: i32) -> std::io::Result<()> {
+	#[cfg(target_os = "linux")]
+	{
+		// SAFETY: plain syscall with no pointers.
+		//
+		// On Linux, `PRIO_PROCESS` with `0` applies
+		// to the calling thread, not the whole process.
+		let result = unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) };
+		if result != 0 {
+			return Err(std::io::Error::last_os_error());
+		}
+	}
+
+	Ok(())
+}
+
+#[macro_export]
+/// Pin the current thread to a single CPU core
+///
+/// This calls [`set_affinity`](crate::thread::set_affinity) and returns a [`std::io::Result`].
+///
+/// This is a no-op that returns [`Ok`] on non-Linux platforms.
+///
+/// ```rust
+/// # use benri::thread::*;
+/// std::thread::spawn(|| {
+///     let core = current_affinity!().unwrap()[0];
+///     pin_to_core!(core).unwrap();
+///
+///     #[cfg(target_os = "linux")]
+///     assert!(current_affinity!().unwrap() == [core]);
+/// }).join().unwrap();
+/// ```
+macro_rules! pin_to_core {
+	($core:expr) => {
+		$crate::thread::set_affinity(&[$core])
+	}
+}
+pub use pin_to_core;
+
+#[macro_export]
+/// Get the CPU cores the current thread can run on as a `std::io::Result<Vec<usize>>`
+///
+/// This calls [`affinity`](crate::thread::affinity).
+///
+/// ```rust
+/// # use benri::thread::*;
+/// assert!(!current_affinity!().unwrap().is_empty());
+/// ```
+macro_rules! current_affinity {
+	() => {
+		$crate::thread::affinity()
+	}
+}
+pub use current_affinity;
+
+#[macro_export]
+/// Set the nice value of the current thread
+///
+/// This calls [`set_priority`](crate::thread::set_priority) and returns a [`std::io::Result`].
+///
+/// This is a no-op that returns [`Ok`] on non-Linux platforms.
+///
+/// ```rust
+/// # use benri::thread::*;
+/// std::thread::spawn(|| {
+///     // Raising the nice value (lowering priority) is always allowed.
+///     set_nice!(19).unwrap();
+/// }).join().unwrap();
+/// ```
+macro_rules! set_nice {
+	($nice:expr) => {
+		$crate::thread::set_priority($nice)
+	}
+}
+pub use set_nice;
+
+
+//---------------------------------------------------------------------------------------------------- Join
+/// The payload of a panicked thread, with its message extracted
+///
+/// The message is the `&str` or `String` the thread panicked with,
+/// or `<non-string panic payload>` for anything else.
+pub struct PanicPayload {
+	message: String,
+	payload: Box<dyn Any + Send + 'static>,
+}
+
+impl PanicPayload {
+	#[inline]
+	/// The panic message
+	pub fn message(&self) -> &str {
+		&self.message
+	}
+
+	#[inline]
+	/// Return the original payload, e.g. for [`std::panic::resume_unwind`]
+	pub fn into_inner(self) -> Box<dyn Any + Send + 'static> {
+		self.payload
+	}
+}
+
+impl From<Box<dyn Any + Send + 'static>> for PanicPayload {
+	fn from(payload: Box<dyn Any + Send + 'static>) -> Self {
+		Self {
+			message: panic_message(&*payload).to_string(),
+			payload,
+		}
+	}
+}
+
+impl std::fmt::Debug for PanicPayload {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_tuple("PanicPayload").field(&self.message).finish()
+	}
+}
+
+impl std::fmt::Display for PanicPayload {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(&self.message)
+	}
+}
+
+impl std::error::Error for PanicPayload {}
+
+/// Join all `handles` in order
+///
+/// This is what [`join_all!`] calls.
+pub fn join_handles<T, I>(handles: I) -> Vec<Result<T, PanicPayload>>
+where
+	I: IntoIterator<Item = JoinHandle<T>>,
+{
+	handles
+		.into_iter()
+		.map(|h| h.join().map_err(PanicPayload::from))
+		.collect()
+}
+
+/// Join `handle`, giving up after `timeout`
+///
+/// The standard library has no timed join, so this polls
+/// [`JoinHandle::is_finished`] every millisecond.
+///
+/// This is what [`join_timeout!`] calls.
+///
+/// # Errors
+/// If `timeout` passes, the [`JoinHandle`] is given back in [`Err`]
+/// so that it can be joined again later.
+pub fn join_with_timeout<T>(handle: JoinHandle<T>, timeout: Duration) -> Result<Result<T, PanicPayload>, JoinHandle<T>> {
+	let deadline = Instant::now() + timeout;
+	loop {
+		if handle.is_finished() {
+			return Ok(handle.join().map_err(PanicPayload::from));
+		}
+
+		let remaining = deadline.saturating_duration_since(Instant::now());
+		if remaining.is_zero() {
+			return Err(handle);
+		}
+		std::thread::sleep(remaining.min(Duration::from_millis(1)));
+	}
+}
+
+#[macro_export]
+/// Join all [`std::thread::JoinHandle`]'s, returning a `Vec<Result<T, PanicPayload>>`
+///
+/// The results are in the same order as the handles.
+///
+/// See [`PanicPayload`](crate::thread::PanicPayload) and [`join_handles`](crate::thread::join_handles).
+///
+/// ```rust
+/// # use benri::thread::*;
+/// let handles = vec![
+///     std::thread::spawn(|| 1),
+///     std::thread::spawn(|| panic!("oops")),
+///     std::thread::spawn(|| 3),
+/// ];
+///
+/// let results = join_all!(handles);
+/// assert!(*results[0].as_ref().unwrap() == 1);
+/// assert!(results[1].as_ref().unwrap_err().message() == "oops");
+/// assert!(*results[2].as_ref().unwrap() == 3);
+/// ```
+macro_rules! join_all {
+	($handles:expr) => {
+		$crate::thread::join_handles($handles)
+	}
+}
+pub use join_all;
+
+#[macro_export]
+/// `.join()` a [`std::thread::JoinHandle`], [`mass_panic!`](crate::panic::mass_panic) if the thread panicked
+///
+/// The thread's name and panic message are included in the output.
+///
+/// ```rust
+/// # use benri::thread::*;
+/// let handle = std::thread::spawn(|| 123);
+/// assert!(join_or_mass!(handle) == 123);
+/// ```
+macro_rules! join_or_mass {
+	($handle:expr) => {{
+		let handle = $handle;
+		let name = match handle.thread().name() {
+			::std::option::Option::Some(name) => ::std::string::ToString::to_string(name),
+			::std::option::Option::None       => ::std::format!("{:?}", handle.thread().id()),
+		};
+		match handle.join() {
+			::std::result::Result::Ok(t)  => t,
+			::std::result::Result::Err(e) => {
+				let e = $crate::thread::PanicPayload::from(e);
+				$crate::mass_panic!("THREAD PANIC [{}] - {}", name, e);
+			},
+		}
+	}}
+}
+pub use join_or_mass;
+
+#[macro_export]
+/// Join a [`std::thread::JoinHandle`], giving up after a [`std::time::Duration`]
+///
+/// This returns `Result<Result<T, PanicPayload>, JoinHandle<T>>`,
+/// see [`join_with_timeout`](crate::thread::join_with_timeout).
+///
+/// ```rust
+/// # use benri::thread::*;
+/// # use std::time::Duration;
+/// let handle = std::thread::spawn(|| std::thread::sleep(Duration::from_millis(100)));
+///
+/// // Too early.
+/// let handle = join_timeout!(handle, Duration::from_millis(1)).unwrap_err();
+///
+/// // Finished.
+/// assert!(join_timeout!(handle, Duration::from_secs(10)).unwrap().is_ok());
+/// ```
+macro_rules! join_timeout {
+	($handle:expr, $timeout:expr) => {
+		$crate::thread::join_with_timeout($handle, $timeout)
+	}
+}
+pub use join_timeout;
@@ -1,78 +1,271 @@
+//---------------------------------------------------------------------------------------------------- Use
+use std::fmt;
+use std::str::FromStr;
+use std::cell::RefCell;
+use std::sync::atomic::{
+	AtomicUsize,
+	Ordering,
+};
+use std::sync::{
+	Arc,
+	Mutex,
+	RwLock,
+};
+use std::time::{
+	Duration,
+	Instant,
+	SystemTime,
+	SystemTimeError,
+};
+
 //---------------------------------------------------------------------------------------------------- Time
 #[macro_export]
-/// Expands to [`std::time::Instant::now()`]
+/// Get the current [`std::time::Instant`]
+///
+/// This is [`std::time::Instant::now()`], unless a [`Clock`](crate::time::Clock) override is set.
 macro_rules! now {
 	() => {
-		::std::time::Instant::now()
+		$crate::time::clock_now()
 	}
 }
 pub use now;
 
 #[macro_export]
-/// Calls [`std::time::Instant::elapsed`] and [`std::time::Duration::as_secs`]
+/// Calls [`elapsed()`](crate::time::elapsed) and [`std::time::Duration::as_secs`]
+///
+/// This (and the other elapsed macros) accept an [`std::time::Instant`] or a reference to one.
+///
+/// ```rust
+/// # use benri::time::*;
+/// let now = now!();
+///
+/// assert!(secs!(now) < 60);
+/// assert!(secs!(&now) < 60);
+/// ```
 macro_rules! secs {
 	($t:expr) => {
-		$t.elapsed().as_secs()
+		$crate::time::elapsed(*::std::borrow::Borrow::<::std::time::Instant>::borrow(&$t)).as_secs()
 	}
 }
 pub use secs;
 
 #[macro_export]
-/// Calls [`std::time::Instant::elapsed`] and [`std::time::Duration::as_secs_f32()`]
+/// Calls [`elapsed()`](crate::time::elapsed) and [`std::time::Duration::as_secs_f32()`]
 macro_rules! secs_f32 {
 	($t:expr) => {
-		$t.elapsed().as_secs_f32()
+		$crate::time::elapsed(*::std::borrow::Borrow::<::std::time::Instant>::borrow(&$t)).as_secs_f32()
 	}
 }
 pub use secs_f32;
 
 #[macro_export]
-/// Calls [`std::time::Instant::elapsed`] and [`std::time::Duration::as_secs_f64()`]
+/// Calls [`elapsed()`](crate::time::elapsed) and [`std::time::Duration::as_secs_f64()`]
 macro_rules! secs_f64 {
 	($t:expr) => {
-		$t.elapsed().as_secs_f64()
+		$crate::time::elapsed(*::std::borrow::Borrow::<::std::time::Instant>::borrow(&$t)).as_secs_f64()
 	}
 }
 pub use secs_f64;
 
 #[macro_export]
-/// Calls [`std::time::Instant::elapsed`] and [`std::time::Duration::as_millis()`]
+/// Calls [`elapsed()`](crate::time::elapsed) and [`std::time::Duration::as_millis()`]
 macro_rules! millis {
 	($t:expr) => {
-		$t.elapsed().as_millis()
+		$crate::time::elapsed(*::std::borrow::Borrow::<::std::time::Instant>::borrow(&$t)).as_millis()
 	}
 }
 pub use millis;
 
 #[macro_export]
-/// Calls [`std::time::Instant::elapsed`] and [`std::time::Duration::as_micros()`]
+/// Calls [`elapsed()`](crate::time::elapsed) and [`std::time::Duration::as_micros()`]
 macro_rules! micros {
 	($t:expr) => {
-		$t.elapsed().as_micros()
+		$crate::time::elapsed(*::std::borrow::Borrow::<::std::time::Instant>::borrow(&$t)).as_micros()
 	}
 }
 pub use micros;
 
 #[macro_export]
-/// Calls [`std::time::Instant::elapsed`] and [`std::time::Duration::as_nanos()`]
+/// Calls [`elapsed()`](crate::time::elapsed) and [`std::time::Duration::as_nanos()`]
 macro_rules! nanos {
 	($t:expr) => {
-		$t.elapsed().as_nanos()
+		$crate::time::elapsed(*::std::borrow::Borrow::<::std::time::Instant>::borrow(&$t)).as_nanos()
 	}
 }
 pub use nanos;
 
+
+#[macro_export]
+/// Get the seconds elapsed [`std::time::UNIX_EPOCH`]
+///
+/// This uses the [`Clock`](crate::time::Clock) override if set.
+///
+/// ```rust
+/// # use benri::time::*;
+/// let now = unix!();
+///
+/// assert!(now > 1680460287);
+/// ```
+///
+/// # Error
+/// This calls [`std::time::SystemTime::now`] then [`std::time::SystemTime::duration_since`].
+///
+/// Those calls might error if the system clock is wrong.
+///
+/// On error, this macro will silently return `0`.
+macro_rules! unix {
+	() => {
+		$crate::time::clock_system_time()
+			.duration_since(::std::time::SystemTime::UNIX_EPOCH)
+			.unwrap_or(::std::time::Duration::ZERO)
+			.as_secs()
+	}
+}
+pub use unix;
+
+#[macro_export]
+/// Get the milliseconds elapsed [`std::time::UNIX_EPOCH`]
+///
+/// ```rust
+/// # use benri::time::*;
+/// let now = unix_millis!();
+///
+/// assert!(now > 1680460287000);
+/// ```
+///
+/// # Error
+/// Like [`unix!`], this will silently return `0` on error.
+macro_rules! unix_millis {
+	() => {
+		$crate::time::clock_system_time()
+			.duration_since(::std::time::SystemTime::UNIX_EPOCH)
+			.unwrap_or(::std::time::Duration::ZERO)
+			.as_millis()
+	}
+}
+pub use unix_millis;
+
+#[macro_export]
+/// Get the nanoseconds elapsed [`std::time::UNIX_EPOCH`]
+///
+/// ```rust
+/// # use benri::time::*;
+/// let now = unix_nanos!();
+///
+/// assert!(now > 1680460287000000000);
+/// ```
+///
+/// # Error
+/// Like [`unix!`], this will silently return `0` on error.
+macro_rules! unix_nanos {
+	() => {
+		$crate::time::clock_system_time()
+			.duration_since(::std::time::SystemTime::UNIX_EPOCH)
+			.unwrap_or(::std::time::Duration::ZERO)
+			.as_nanos()
+	}
+}
+pub use unix_nanos;
+
+#[macro_export]
+/// Get the seconds elapsed [`std::time::UNIX_EPOCH`] as a [`Result`]
+///
+/// This is the same as [`unix!`], except the [`std::time::SystemTimeError`] is returned on error.
+///
+/// ```rust
+/// # use benri::time::*;
+/// let now = unix_result!().unwrap();
+///
+/// assert!(now > 1680460287);
+/// ```
+macro_rules! unix_result {
+	() => {
+		$crate::time::clock_system_time()
+			.duration_since(::std::time::SystemTime::UNIX_EPOCH)
+			.map(|d| d.as_secs())
+	}
+}
+pub use unix_result;
+
+//---------------------------------------------------------------------------------------------------- Duration
+#[macro_export]
+/// Create a [`std::time::Duration`] from units, evaluated at compile time
+///
+/// The input is any amount of `<number> <unit>` pairs, which are added together.
+///
+/// Numbers may be integers or decimals (`1`, `1_000`, `1.5`).
+///
+/// | Unit         | Accepted                                    |
+/// |--------------|---------------------------------------------|
+/// | Week         | `w`, `week`, `weeks`                        |
+/// | Day          | `d`, `day`, `days`                          |
+/// | Hour         | `h`, `hr`, `hrs`, `hour`, `hours`           |
+/// | Minute       | `m`, `min`, `mins`, `minute`, `minutes`     |
+/// | Second       | `s`, `sec`, `secs`, `second`, `seconds`     |
+/// | Millisecond  | `ms`, `msec`, `millisecond`, `milliseconds` |
+/// | Microsecond  | `us`, `usec`, `microsecond`, `microseconds` |
+/// | Nanosecond   | `ns`, `nsec`, `nanosecond`, `nanoseconds`   |
+///
+/// ```rust
+/// # use benri::time::*;
+/// # use std::time::Duration;
+/// assert_eq!(dur!(1.5 s),     Duration::from_millis(1500));
+/// assert_eq!(dur!(250 ms),    Duration::from_millis(250));
+/// assert_eq!(dur!(2 h 30 m),  Duration::from_secs(9000));
+/// assert_eq!(dur!(1 day 1 s), Duration::from_secs(86_401));
+///
+/// // Usable in `const`.
+/// const TIMEOUT: Duration = dur!(5 s);
+/// ```
+///
+/// Unknown units and invalid numbers fail to compile:
+/// ```rust,compile_fail
+/// # use benri::time::*;
+/// let d = dur!(5 parsecs);
+/// ```
+/// ```rust,compile_fail
+/// # use benri::time::*;
+/// let d = dur!(1e3 ms);
+/// ```
+macro_rules! dur {
+	($($number:literal $unit:ident)+) => {
+		const { $crate::time::dur_const(&[$((::std::stringify!($number), ::std::stringify!($unit))),+]) }
+	}
+}
+pub use dur;
+
 #[macro_export]
-/// Expands to [`std::time::Duration::from_secs(1)`]
+/// Expands to `Duration::from_secs(1)`
+///
+/// A fraction can be passed, e.g: `second!(1 / 3)`, which is evaluated at compile time.
+///
+/// ```rust
+/// # use benri::time::*;
+/// # use std::time::Duration;
+/// assert_eq!(second!(), Duration::from_secs(1));
+/// assert_eq!(second!(3 / 4), Duration::from_millis(750));
+///
+/// // Usable in `const`.
+/// const HALF: Duration = second!(1 / 2);
+/// ```
+///
+/// Fractions are evaluated at compile time, so a `0` denominator fails to compile:
+/// ```rust,compile_fail
+/// # use benri::time::*;
+/// let d = second!(1 / 0);
+/// ```
 macro_rules! second {
 	() => {
 		::std::time::Duration::from_secs(1)
-	}
+	};
+	($numerator:literal / $denominator:literal) => {
+		const { $crate::time::dur_fraction(1_000_000_000, $numerator, $denominator) }
+	};
 }
 pub use second;
 
 #[macro_export]
-/// Expands to [`std::time::Duration::from_millis(500)`]
+/// Expands to `Duration::from_millis(500)`
 macro_rules! half_second {
 	() => {
 		::std::time::Duration::from_millis(500)
@@ -81,7 +274,7 @@ macro_rules! half_second {
 pub use half_second;
 
 #[macro_export]
-/// Expands to [`std::time::Duration::from_millis(333)`]
+/// Expands to `Duration::from_millis(333)`
 macro_rules! third_second {
 	() => {
 		::std::time::Duration::from_millis(333)
@@ -90,7 +283,7 @@ macro_rules! third_second {
 pub use third_second;
 
 #[macro_export]
-/// Expands to [`std::time::Duration::from_millis(250)`]
+/// Expands to `Duration::from_millis(250)`
 macro_rules! quarter_second {
 	() => {
 		::std::time::Duration::from_millis(250)
@@ -99,36 +292,1995 @@ macro_rules! quarter_second {
 pub use quarter_second;
 
 #[macro_export]
-/// Expands to [`std::time::Duration::from_secs(60)`]
+/// Expands to `Duration::from_secs(60)`
+///
+/// A fraction can be passed, e.g: `minute!(1 / 3)`.
+///
+/// ```rust
+/// # use benri::time::*;
+/// # use std::time::Duration;
+/// assert_eq!(minute!(), Duration::from_secs(60));
+/// assert_eq!(minute!(1 / 3), Duration::from_secs(20));
+/// ```
 macro_rules! minute {
 	() => {
 		::std::time::Duration::from_secs(60)
-	}
+	};
+	($numerator:literal / $denominator:literal) => {
+		const { $crate::time::dur_fraction(60_000_000_000, $numerator, $denominator) }
+	};
 }
 pub use minute;
 
 #[macro_export]
-/// Get the seconds elapsed [`std::time::UNIX_EPOCH`]
+/// Expands to `Duration::from_secs(3600)`
+///
+/// A fraction can be passed, e.g: `hour!(1 / 4)`.
 ///
 /// ```rust
 /// # use benri::time::*;
-/// let now = unix!();
+/// # use std::time::Duration;
+/// assert_eq!(hour!(), Duration::from_secs(3600));
+/// assert_eq!(hour!(1 / 4), Duration::from_secs(900));
+/// ```
+macro_rules! hour {
+	() => {
+		::std::time::Duration::from_secs(3_600)
+	};
+	($numerator:literal / $denominator:literal) => {
+		const { $crate::time::dur_fraction(3_600_000_000_000, $numerator, $denominator) }
+	};
+}
+pub use hour;
+
+#[macro_export]
+/// Expands to `Duration::from_secs(86400)`
 ///
-/// assert!(now > 1680460287);
+/// A fraction can be passed, e.g: `day!(1 / 2)`.
+///
+/// ```rust
+/// # use benri::time::*;
+/// # use std::time::Duration;
+/// assert_eq!(day!(), Duration::from_secs(86_400));
+/// assert_eq!(day!(1 / 2), Duration::from_secs(43_200));
 /// ```
+macro_rules! day {
+	() => {
+		::std::time::Duration::from_secs(86_400)
+	};
+	($numerator:literal / $denominator:literal) => {
+		const { $crate::time::dur_fraction(86_400_000_000_000, $numerator, $denominator) }
+	};
+}
+pub use day;
+
+#[macro_export]
+/// Expands to `Duration::from_secs(604800)`
 ///
-/// # Error
-/// This calls [`std::time::SystemTime::now`] then [`std::time::SystemTime::duration_since`].
+/// A fraction can be passed, e.g: `week!(1 / 7)`.
 ///
-/// Those calls might error if the system clock is wrong.
+/// ```rust
+/// # use benri::time::*;
+/// # use std::time::Duration;
+/// assert_eq!(week!(), Duration::from_secs(604_800));
+/// assert_eq!(week!(1 / 7), day!());
+/// ```
 ///
-/// On error, this macro will silently return `0`.
-macro_rules! unix {
+/// Overflowing fractions fail to compile:
+/// ```rust,compile_fail
+/// # use benri::time::*;
+/// let d = week!(18446744073709551615 / 1);
+/// ```
+macro_rules! week {
 	() => {
-		::std::time::SystemTime::now()
-			.duration_since(::std::time::SystemTime::UNIX_EPOCH)
-			.unwrap_or(::std::time::Duration::ZERO)
-			.as_secs()
+		::std::time::Duration::from_secs(604_800)
+	};
+	($numerator:literal / $denominator:literal) => {
+		const { $crate::time::dur_fraction(604_800_000_000_000, $numerator, $denominator) }
+	};
+}
+pub use week;
+
+#[doc(hidden)]
+/// Used by [`dur!`], parses `(number, unit)` pairs.
+///
+/// This panics (fails to compile in `const`) on invalid input.
+pub const fn dur_const(parts: &[(&str, &str)]) -> Duration {
+	let mut total: u128 = 0;
+	let mut i = 0;
+	while i < parts.len() {
+		let unit = match unit_nanos(parts[i].1.as_bytes()) {
+			Some(u) => u,
+			None    => panic!("dur!: unknown unit"),
+		};
+		let nanos = match number_nanos(parts[i].0.as_bytes(), unit) {
+			Ok(n)                      => n,
+			Err(NumberError::Invalid)  => panic!("dur!: invalid number"),
+			Err(NumberError::Overflow) => panic!("dur!: overflow"),
+		};
+		total = match total.checked_add(nanos) {
+			Some(t) => t,
+			None    => panic!("dur!: overflow"),
+		};
+		i += 1;
 	}
+
+	nanos_to_duration(total)
 }
-pub use unix;
+
+#[doc(hidden)]
+/// Used by the fraction form of [`second!`], [`minute!`], etc.
+pub const fn dur_fraction(unit_nanos: u128, numerator: u64, denominator: u64) -> Duration {
+	assert!(denominator != 0, "duration fraction: denominator is 0");
+	nanos_to_duration(unit_nanos * numerator as u128 / denominator as u128)
+}
+
+// Convert nanoseconds to a `Duration`, panics on overflow.
+const fn nanos_to_duration(nanos: u128) -> Duration {
+	let secs = nanos / 1_000_000_000;
+	assert!(secs <= u64::MAX as u128, "duration overflow");
+	Duration::new(secs as u64, (nanos % 1_000_000_000) as u32)
+}
+
+// Map a (lowercase) unit to its length in nanoseconds.
+const fn unit_nanos(unit: &[u8]) -> Option<u128> {
+	let nanos = match unit {
+		b"w"|b"week"|b"weeks"                                  => 604_800_000_000_000,
+		b"d"|b"day"|b"days"                                    => 86_400_000_000_000,
+		b"h"|b"hr"|b"hrs"|b"hour"|b"hours"                     => 3_600_000_000_000,
+		b"m"|b"min"|b"mins"|b"minute"|b"minutes"               => 60_000_000_000,
+		b"s"|b"sec"|b"secs"|b"second"|b"seconds"               => 1_000_000_000,
+		b"ms"|b"msec"|b"millisecond"|b"milliseconds"           => 1_000_000,
+		b"us"|b"\xC2\xB5s"|b"usec"|b"microsecond"|b"microseconds" => 1_000, // `\xC2\xB5` == `µ`
+		b"ns"|b"nsec"|b"nanosecond"|b"nanoseconds"             => 1,
+		_ => return None,
+	};
+	Some(nanos)
+}
+
+// Error from `number_nanos()`.
+enum NumberError {
+	Invalid,
+	Overflow,
+}
+
+// Parse `number` (digits, `_`, and an optional `.` fraction) and multiply by `unit_nanos`.
+//
+// Anything past nanosecond precision is ignored.
+const fn number_nanos(number: &[u8], unit_nanos: u128) -> Result<u128, NumberError> {
+	let mut whole: u128 = 0;
+	let mut frac:  u128 = 0;
+	let mut scale = unit_nanos;
+	let mut digits = 0;
+	let mut dot = false;
+
+	let mut i = 0;
+	while i < number.len() {
+		let b = number[i];
+		i += 1;
+
+		if b == b'_' {
+			continue;
+		} else if b == b'.' {
+			if dot {
+				return Err(NumberError::Invalid);
+			}
+			dot = true;
+			continue;
+		} else if !b.is_ascii_digit() {
+			return Err(NumberError::Invalid);
+		}
+
+		digits += 1;
+		let d = (b - b'0') as u128;
+		if dot {
+			scale /= 10;
+			frac += d * scale;
+		} else {
+			whole = match whole.checked_mul(10) {
+				Some(w) => match w.checked_add(d) {
+					Some(w) => w,
+					None    => return Err(NumberError::Overflow),
+				},
+				None => return Err(NumberError::Overflow),
+			};
+		}
+	}
+
+	if digits == 0 {
+		return Err(NumberError::Invalid);
+	}
+
+	match whole.checked_mul(unit_nanos) {
+		Some(w) => match w.checked_add(frac) {
+			Some(n) => Ok(n),
+			None    => Err(NumberError::Overflow),
+		},
+		None => Err(NumberError::Overflow),
+	}
+}
+
+//---------------------------------------------------------------------------------------------------- Timing
+#[macro_export]
+/// Evaluate a block, return its value and the elapsed [`std::time::Duration`]
+///
+/// ```rust
+/// # use benri::time::*;
+/// let (value, elapsed) = timed!({
+///     std::thread::sleep(std::time::Duration::from_millis(10));
+///     123
+/// });
+///
+/// assert!(value == 123);
+/// assert!(elapsed.as_millis() >= 10);
+/// ```
+macro_rules! timed {
+	($block:block) => {{
+		let now = $crate::now!();
+		let value = $block;
+		(value, $crate::time::elapsed(now))
+	}}
+}
+pub use timed;
+
+#[macro_export]
+/// Evaluate a block, report the elapsed time with a label, and return its value
+///
+/// If the `log` feature flag is enabled, this reports with `ok_debug!()`,
+/// else it uses [`std::eprintln`].
+///
+/// An optional [`std::time::Duration`] threshold can be passed before the block,
+/// in which case the elapsed time is only reported if it is `>=` the threshold.
+///
+/// ```rust
+/// # use benri::time::*;
+/// let value = time_block!("add", {
+///     1 + 1
+/// });
+/// assert!(value == 2);
+///
+/// // This is fast, so it will not be reported.
+/// let value = time_block!("add", second!(), {
+///     1 + 1
+/// });
+/// assert!(value == 2);
+/// ```
+macro_rules! time_block {
+	($label:expr, $block:block) => {{
+		let (value, elapsed) = $crate::timed!($block);
+		#[cfg(feature = "log")]
+		$crate::ok_debug!("{} ... {:?}", $label, elapsed);
+		#[cfg(not(feature = "log"))]
+		::std::eprintln!("{} ... {:?}", $label, elapsed);
+		value
+	}};
+
+	($label:expr, $threshold:expr, $block:block) => {{
+		let (value, elapsed) = $crate::timed!($block);
+		if elapsed >= $threshold {
+			#[cfg(feature = "log")]
+			$crate::ok_debug!("{} ... {:?}", $label, elapsed);
+			#[cfg(not(feature = "log"))]
+			::std::eprintln!("{} ... {:?}", $label, elapsed);
+		}
+		value
+	}};
+}
+pub use time_block;
+
+//---------------------------------------------------------------------------------------------------- Stopwatch
+#[derive(Clone,Debug,PartialEq,Eq)]
+/// A single lap recorded by a [`Stopwatch`]
+pub struct Lap {
+	/// The label given to [`Stopwatch::lap`]
+	pub label: String,
+	/// Time elapsed since the previous lap (or the start)
+	pub split: Duration,
+	/// Total time elapsed on the [`Stopwatch`] when this lap was recorded
+	pub total: Duration,
+}
+
+#[derive(Clone,Debug)]
+/// A pausable stopwatch that records laps
+///
+/// Paused time is not counted towards [`Stopwatch::elapsed`].
+///
+/// If [`Stopwatch::report_on_drop`] was set, the total elapsed time
+/// will be reported when the [`Stopwatch`] is dropped, either with
+/// `ok_debug!()` if the `log` feature flag is enabled, else [`std::eprintln`].
+///
+/// ```rust
+/// # use benri::time::*;
+/// let mut s = Stopwatch::start();
+///
+/// std::thread::sleep(std::time::Duration::from_millis(10));
+/// s.lap("first");
+///
+/// s.pause();
+/// std::thread::sleep(std::time::Duration::from_millis(100));
+/// s.resume();
+///
+/// std::thread::sleep(std::time::Duration::from_millis(10));
+/// s.lap("second");
+///
+/// let laps: Vec<&Lap> = s.laps().collect();
+/// assert!(laps.len() == 2);
+/// assert!(laps[0].label == "first");
+/// assert!(laps[1].split.as_millis() >= 10);
+/// assert!(laps[1].split.as_millis() < 100);
+/// assert!(s.elapsed().as_millis() >= 20);
+/// ```
+pub struct Stopwatch {
+	// Time accumulated before the current running segment.
+	acc: Duration,
+	// `Some` if running, `None` if paused.
+	since: Option<Instant>,
+	// Total elapsed at the last lap.
+	last_lap: Duration,
+	laps: Vec<Lap>,
+	report: Option<String>,
+}
+
+impl Stopwatch {
+	#[inline]
+	/// Create a new, running [`Stopwatch`]
+	pub fn start() -> Self {
+		Self {
+			acc: Duration::ZERO,
+			since: Some(crate::now!()),
+			last_lap: Duration::ZERO,
+			laps: Vec::new(),
+			report: None,
+		}
+	}
+
+	#[inline]
+	#[must_use]
+	/// Report the total elapsed time with `label` when this [`Stopwatch`] is dropped
+	pub fn report_on_drop<S: Into<String>>(mut self, label: S) -> Self {
+		self.report = Some(label.into());
+		self
+	}
+
+	#[inline]
+	/// Pause the [`Stopwatch`], this does nothing if already paused
+	pub fn pause(&mut self) {
+		if let Some(since) = self.since.take() {
+			self.acc += elapsed(since);
+		}
+	}
+
+	#[inline]
+	/// Resume the [`Stopwatch`], this does nothing if already running
+	pub fn resume(&mut self) {
+		if self.since.is_none() {
+			self.since = Some(crate::now!());
+		}
+	}
+
+	#[inline]
+	/// Returns `true` if the [`Stopwatch`] is paused
+	pub fn is_paused(&self) -> bool {
+		self.since.is_none()
+	}
+
+	#[inline]
+	/// Total time elapsed, not counting paused time
+	pub fn elapsed(&self) -> Duration {
+		match self.since {
+			Some(since) => self.acc + elapsed(since),
+			None        => self.acc,
+		}
+	}
+
+	/// Record a lap, returning the split time since the previous lap
+	pub fn lap<S: Into<String>>(&mut self, label: S) -> Duration {
+		let total = self.elapsed();
+		let split = total.saturating_sub(self.last_lap);
+		self.last_lap = total;
+		self.laps.push(Lap { label: label.into(), split, total });
+		split
+	}
+
+	#[inline]
+	/// Iterate over all recorded [`Lap`]'s
+	pub fn laps(&self) -> std::slice::Iter<'_, Lap> {
+		self.laps.iter()
+	}
+
+	/// Clear all laps and restart the [`Stopwatch`] from zero
+	///
+	/// The [`Stopwatch`] will be running after this, even if it was paused.
+	pub fn reset(&mut self) {
+		self.acc      = Duration::ZERO;
+		self.since    = Some(crate::now!());
+		self.last_lap = Duration::ZERO;
+		self.laps.clear();
+	}
+}
+
+impl Default for Stopwatch {
+	#[inline]
+	fn default() -> Self {
+		Self::start()
+	}
+}
+
+impl Drop for Stopwatch {
+	fn drop(&mut self) {
+		if let Some(label) = &self.report {
+			#[cfg(feature = "log")]
+			crate::ok_debug!("{} ... {:?}", label, self.elapsed());
+			#[cfg(not(feature = "log"))]
+			::std::eprintln!("{} ... {:?}", label, self.elapsed());
+		}
+	}
+}
+
+#[macro_export]
+/// Create a running [`Stopwatch`](crate::time::Stopwatch)
+///
+/// If a label is given, the total elapsed time will be reported on drop,
+/// see [`Stopwatch::report_on_drop`](crate::time::Stopwatch::report_on_drop).
+///
+/// ```rust
+/// # use benri::time::*;
+/// let s = stopwatch!();
+/// assert!(!s.is_paused());
+///
+/// let s = stopwatch!("reported on drop");
+/// ```
+macro_rules! stopwatch {
+	() => {
+		$crate::time::Stopwatch::start()
+	};
+	($label:expr) => {
+		$crate::time::Stopwatch::start().report_on_drop($label)
+	};
+}
+pub use stopwatch;
+
+#[macro_export]
+/// Record a lap on a [`Stopwatch`](crate::time::Stopwatch)
+///
+/// If no label is given, the call-site `file:line` is used.
+///
+/// ```rust
+/// # use benri::time::*;
+/// let mut s = stopwatch!();
+///
+/// lap!(s, "first");
+/// lap!(s);
+///
+/// let laps: Vec<&Lap> = s.laps().collect();
+/// assert!(laps[0].label == "first");
+/// assert!(laps[1].label.contains(".rs:"));
+/// ```
+macro_rules! lap {
+	($stopwatch:expr) => {
+		$stopwatch.lap(::std::concat!(::std::file!(), ":", ::std::line!()))
+	};
+	($stopwatch:expr, $label:expr) => {
+		$stopwatch.lap($label)
+	};
+}
+pub use lap;
+
+//---------------------------------------------------------------------------------------------------- Human duration
+#[derive(Copy,Clone,Debug,PartialEq,Eq,PartialOrd,Ord,Hash)]
+/// A [`std::fmt::Display`] wrapper around a [`Duration`] for human-readable output
+///
+/// The compact form looks like `1h 2m 3.5s`, the verbose form
+/// looks like `1 hour, 2 minutes, 3.5 seconds`.
+///
+/// Durations under a second use the largest fitting unit out of
+/// milliseconds, microseconds and nanoseconds.
+///
+/// Fractions are truncated to 3 decimal places.
+///
+/// ```rust
+/// # use benri::time::*;
+/// # use std::time::Duration;
+/// let d = Duration::from_millis(3_723_500);
+///
+/// assert_eq!(HumanDuration::new(d).to_string(),     "1h 2m 3.5s");
+/// assert_eq!(HumanDuration::verbose(d).to_string(), "1 hour, 2 minutes, 3.5 seconds");
+///
+/// assert_eq!(HumanDuration::new(Duration::from_micros(1500)).to_string(), "1.5ms");
+/// assert_eq!(HumanDuration::new(Duration::ZERO).to_string(), "0s");
+///
+/// // Truncated seconds are not written as `0s`.
+/// let d = Duration::from_nanos(60_000_000_500);
+/// assert_eq!(HumanDuration::new(d).to_string(),     "1m");
+/// assert_eq!(HumanDuration::verbose(d).to_string(), "1 minute");
+/// ```
+pub struct HumanDuration {
+	duration: Duration,
+	verbose: bool,
+}
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+/// Error returned when parsing a human-readable duration fails
+///
+/// See [`HumanDuration::parse`].
+pub enum ParseDurationError {
+	/// The input was empty
+	Empty,
+	/// A number could not be parsed
+	InvalidNumber(String),
+	/// A number was not followed by a unit
+	MissingUnit(String),
+	/// The unit is unknown
+	UnknownUnit(String),
+	/// The duration does not fit in a [`Duration`]
+	Overflow,
+}
+
+impl fmt::Display for ParseDurationError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Empty            => write!(f, "empty duration"),
+			Self::InvalidNumber(s) => write!(f, "invalid number: {s:?}"),
+			Self::MissingUnit(s)   => write!(f, "missing unit after: {s:?}"),
+			Self::UnknownUnit(s)   => write!(f, "unknown unit: {s:?}"),
+			Self::Overflow         => write!(f, "duration overflow"),
+		}
+	}
+}
+
+impl std::error::Error for ParseDurationError {}
+
+// (nanoseconds, compact, verbose singular)
+const UNITS: [(u128, &str, &str); 4] = [
+	(86_400_000_000_000, "d", "day"),
+	(3_600_000_000_000,  "h", "hour"),
+	(60_000_000_000,     "m", "minute"),
+	(1_000_000_000,      "s", "second"),
+];
+const SUB_UNITS: [(u128, &str, &str); 3] = [
+	(1_000_000, "ms", "millisecond"),
+	(1_000,     "µs", "microsecond"),
+	(1,         "ns", "nanosecond"),
+];
+
+impl HumanDuration {
+	#[inline]
+	/// Wrap a [`Duration`] for compact output, e.g: `1h 2m 3.5s`
+	pub const fn new(duration: Duration) -> Self {
+		Self { duration, verbose: false }
+	}
+
+	#[inline]
+	/// Wrap a [`Duration`] for verbose output, e.g: `1 hour, 2 minutes, 3.5 seconds`
+	pub const fn verbose(duration: Duration) -> Self {
+		Self { duration, verbose: true }
+	}
+
+	#[inline]
+	/// Return the inner [`Duration`]
+	pub const fn as_duration(&self) -> Duration {
+		self.duration
+	}
+
+	/// Parse a human-readable duration
+	///
+	/// The input is any amount of `<number><unit>` pairs, optionally
+	/// separated by whitespace or commas. Numbers may have a fraction.
+	///
+	/// | Unit         | Accepted                                               |
+	/// |--------------|--------------------------------------------------------|
+	/// | Week         | `w`, `week`, `weeks`                                   |
+	/// | Day          | `d`, `day`, `days`                                     |
+	/// | Hour         | `h`, `hr`, `hrs`, `hour`, `hours`                      |
+	/// | Minute       | `m`, `min`, `mins`, `minute`, `minutes`                |
+	/// | Second       | `s`, `sec`, `secs`, `second`, `seconds`                |
+	/// | Millisecond  | `ms`, `msec`, `millisecond`, `milliseconds`            |
+	/// | Microsecond  | `us`, `µs`, `usec`, `microsecond`, `microseconds`      |
+	/// | Nanosecond   | `ns`, `nsec`, `nanosecond`, `nanoseconds`              |
+	///
+	/// Units are case-insensitive.
+	///
+	/// ```rust
+	/// # use benri::time::*;
+	/// # use std::time::Duration;
+	/// assert_eq!(HumanDuration::parse("1h30m"),  Ok(Duration::from_secs(5400)));
+	/// assert_eq!(HumanDuration::parse("1.5s"),   Ok(Duration::from_millis(1500)));
+	/// assert_eq!(HumanDuration::parse("1 hour, 2 minutes, 3.5 seconds"), Ok(Duration::from_millis(3_723_500)));
+	///
+	/// assert!(HumanDuration::parse("").is_err());
+	/// assert!(HumanDuration::parse("5").is_err());
+	/// assert!(HumanDuration::parse("5 parsecs").is_err());
+	/// ```
+	pub fn parse(s: &str) -> Result<Duration, ParseDurationError> {
+		let mut s = s.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+		if s.is_empty() {
+			return Err(ParseDurationError::Empty);
+		}
+
+		let mut total: u128 = 0;
+		while !s.is_empty() {
+			// Number.
+			let end = s.find(|c: char| !c.is_ascii_digit() && c != '.' && c != '_').unwrap_or(s.len());
+			let (number, rest) = s.split_at(end);
+
+			// Unit.
+			let rest = rest.trim_start();
+			let end  = rest.find(|c: char| !c.is_alphabetic()).unwrap_or(rest.len());
+			let (unit, rest) = rest.split_at(end);
+
+			if unit.is_empty() {
+				if number.is_empty() {
+					return Err(ParseDurationError::InvalidNumber(rest.to_string()));
+				}
+				return Err(ParseDurationError::MissingUnit(number.to_string()));
+			}
+
+			let unit_nanos = unit_nanos(unit.to_lowercase().as_bytes())
+				.ok_or_else(|| ParseDurationError::UnknownUnit(unit.to_string()))?;
+			let nanos = number_nanos(number.as_bytes(), unit_nanos).map_err(|e| match e {
+				NumberError::Invalid  => ParseDurationError::InvalidNumber(number.to_string()),
+				NumberError::Overflow => ParseDurationError::Overflow,
+			})?;
+			total = total.checked_add(nanos).ok_or(ParseDurationError::Overflow)?;
+
+			s = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+		}
+
+		let secs = u64::try_from(total / 1_000_000_000).map_err(|_| ParseDurationError::Overflow)?;
+		Ok(Duration::new(secs, (total % 1_000_000_000) as u32))
+	}
+}
+
+// Write `nanos / unit` with up to 3 truncated decimal places.
+fn write_fraction(f: &mut fmt::Formatter<'_>, nanos: u128, unit: u128) -> fmt::Result {
+	let whole = nanos / unit;
+	let frac  = (nanos % unit) * 1000 / unit;
+	if frac == 0 {
+		write!(f, "{whole}")
+	} else {
+		let frac = format!("{frac:03}");
+		write!(f, "{whole}.{}", frac.trim_end_matches('0'))
+	}
+}
+
+impl fmt::Display for HumanDuration {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let mut nanos = self.duration.as_nanos();
+
+		if nanos == 0 {
+			return if self.verbose { f.write_str("0 seconds") } else { f.write_str("0s") };
+		}
+
+		// Sub-second durations use a single sub-second unit.
+		if nanos < 1_000_000_000 {
+			let (unit, compact, verbose) = SUB_UNITS.into_iter()
+				.find(|(unit, _, _)| nanos >= *unit)
+				.unwrap_or(SUB_UNITS[2]);
+			write_fraction(f, nanos, unit)?;
+			return if self.verbose {
+				let plural = if nanos == unit { "" } else { "s" };
+				write!(f, " {verbose}{plural}")
+			} else {
+				f.write_str(compact)
+			};
+		}
+
+		let mut first = true;
+		for (unit, compact, verbose) in UNITS {
+			let is_seconds = unit == 1_000_000_000;
+			let amount     = nanos / unit;
+			// Seconds are written with a fraction, so they are
+			// skipped only if they would be written as `0`.
+			let skip = if is_seconds { !first && nanos < unit / 1_000 } else { amount == 0 };
+			if skip {
+				continue;
+			}
+
+			if !first {
+				f.write_str(if self.verbose { ", " } else { " " })?;
+			}
+			first = false;
+
+			if is_seconds {
+				write_fraction(f, nanos, unit)?;
+			} else {
+				write!(f, "{amount}")?;
+			}
+
+			if self.verbose {
+				let singular = if is_seconds { nanos == unit } else { amount == 1 };
+				let plural   = if singular { "" } else { "s" };
+				write!(f, " {verbose}{plural}")?;
+			} else {
+				f.write_str(compact)?;
+			}
+
+			nanos %= unit;
+		}
+
+		Ok(())
+	}
+}
+
+impl From<Duration> for HumanDuration {
+	#[inline]
+	fn from(duration: Duration) -> Self {
+		Self::new(duration)
+	}
+}
+
+impl From<Instant> for HumanDuration {
+	#[inline]
+	/// Uses [`elapsed()`]
+	fn from(instant: Instant) -> Self {
+		Self::new(elapsed(instant))
+	}
+}
+
+impl From<HumanDuration> for Duration {
+	#[inline]
+	fn from(human: HumanDuration) -> Self {
+		human.duration
+	}
+}
+
+impl FromStr for HumanDuration {
+	type Err = ParseDurationError;
+
+	#[inline]
+	/// Uses [`HumanDuration::parse`]
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Self::parse(s).map(Self::new)
+	}
+}
+
+#[macro_export]
+/// Format a [`std::time::Duration`] or [`std::time::Instant`] as human-readable text
+///
+/// This returns a [`HumanDuration`](crate::time::HumanDuration) which implements [`std::fmt::Display`].
+///
+/// [`std::time::Instant`]'s are converted with [`std::time::Instant::elapsed`].
+///
+/// Pass `verbose` as the 2nd argument for the verbose form.
+///
+/// ```rust
+/// # use benri::time::*;
+/// # use std::time::Duration;
+/// let d = Duration::from_secs(90);
+///
+/// assert_eq!(fmt_duration!(d).to_string(), "1m 30s");
+/// assert_eq!(fmt_duration!(d, verbose).to_string(), "1 minute, 30 seconds");
+///
+/// let now = now!();
+/// println!("{}", fmt_duration!(now));
+/// ```
+macro_rules! fmt_duration {
+	($duration:expr) => {
+		$crate::time::HumanDuration::from($duration)
+	};
+	($duration:expr, verbose) => {
+		$crate::time::HumanDuration::verbose($crate::time::HumanDuration::from($duration).as_duration())
+	};
+}
+pub use fmt_duration;
+
+#[macro_export]
+/// Parse a human-readable duration into a [`std::time::Duration`]
+///
+/// This returns a [`Result`] with a [`ParseDurationError`](crate::time::ParseDurationError) on failure.
+///
+/// See [`HumanDuration::parse`](crate::time::HumanDuration::parse) for the accepted format.
+///
+/// ```rust
+/// # use benri::time::*;
+/// # use std::time::Duration;
+/// assert_eq!(parse_duration!("1h30m"), Ok(Duration::from_secs(5400)));
+/// assert_eq!(parse_duration!("250ms"), Ok(Duration::from_millis(250)));
+/// assert!(parse_duration!("soon").is_err());
+/// ```
+macro_rules! parse_duration {
+	($str:expr) => {
+		$crate::time::HumanDuration::parse($str)
+	}
+}
+pub use parse_duration;
+
+//---------------------------------------------------------------------------------------------------- UTC
+#[derive(Copy,Clone,Debug,PartialEq,Eq,PartialOrd,Ord,Hash)]
+/// A UTC calendar date and time
+///
+/// This is a zero-dependency breakdown of a [`SystemTime`] into
+/// `year/month/day/hour/minute/second` in the proleptic Gregorian calendar.
+///
+/// Times before [`std::time::UNIX_EPOCH`] are not supported.
+///
+/// [`std::fmt::Display`] formats as RFC 3339 / ISO 8601, e.g: `2023-04-02T18:31:27Z`.
+///
+/// ```rust
+/// # use benri::time::*;
+/// let utc = UtcTime::from_unix(1680460287);
+///
+/// assert_eq!(utc.year,   2023);
+/// assert_eq!(utc.month,  4);
+/// assert_eq!(utc.day,    2);
+/// assert_eq!(utc.hour,   18);
+/// assert_eq!(utc.minute, 31);
+/// assert_eq!(utc.second, 27);
+/// assert_eq!(utc.to_string(), "2023-04-02T18:31:27Z");
+/// ```
+pub struct UtcTime {
+	/// Year, e.g: `2023`
+	pub year: u64,
+	/// Month, `1..=12`
+	pub month: u8,
+	/// Day of the month, `1..=31`
+	pub day: u8,
+	/// Hour, `0..=23`
+	pub hour: u8,
+	/// Minute, `0..=59`
+	pub minute: u8,
+	/// Second, `0..=59`
+	pub second: u8,
+	/// Nanoseconds past the second, `0..1_000_000_000`
+	pub nanosecond: u32,
+}
+
+impl UtcTime {
+	#[inline]
+	/// Get the current [`UtcTime`]
+	///
+	/// Like [`unix!`](crate::time::unix), this silently returns [`std::time::UNIX_EPOCH`] if the system clock is wrong.
+	pub fn now() -> Self {
+		Self::from_system_time(clock_system_time()).unwrap_or_else(|_| Self::from_unix(0))
+	}
+
+	#[inline]
+	/// Convert a [`SystemTime`] into a [`UtcTime`]
+	///
+	/// # Errors
+	/// This errors if `time` is before [`std::time::UNIX_EPOCH`].
+	pub fn from_system_time(time: SystemTime) -> Result<Self, SystemTimeError> {
+		time.duration_since(SystemTime::UNIX_EPOCH).map(Self::from_duration)
+	}
+
+	#[inline]
+	/// Convert seconds since [`std::time::UNIX_EPOCH`] into a [`UtcTime`]
+	pub fn from_unix(secs: u64) -> Self {
+		Self::from_duration(Duration::from_secs(secs))
+	}
+
+	/// Convert a [`Duration`] since [`std::time::UNIX_EPOCH`] into a [`UtcTime`]
+	pub fn from_duration(since_epoch: Duration) -> Self {
+		let secs = since_epoch.as_secs();
+		let days = secs / 86_400;
+		let rem  = secs % 86_400;
+
+		// Howard Hinnant's `civil_from_days()`, restricted to days >= 0.
+		// <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+		let z   = days + 719_468;
+		let era = z / 146_097;
+		let doe = z - era * 146_097;
+		let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+		let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+		let mp  = (5 * doy + 2) / 153;
+		let day = doy - (153 * mp + 2) / 5 + 1;
+		let month = if mp < 10 { mp + 3 } else { mp - 9 };
+		let year  = yoe + era * 400 + u64::from(month <= 2);
+
+		Self {
+			year,
+			month: month as u8,
+			day: day as u8,
+			hour: (rem / 3_600) as u8,
+			minute: (rem % 3_600 / 60) as u8,
+			second: (rem % 60) as u8,
+			nanosecond: since_epoch.subsec_nanos(),
+		}
+	}
+
+	/// Format as RFC 3339 / ISO 8601 with milliseconds, e.g: `2023-04-02T18:31:27.123Z`
+	pub fn to_string_millis(&self) -> String {
+		format!(
+			"{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+			self.year,
+			self.month,
+			self.day,
+			self.hour,
+			self.minute,
+			self.second,
+			self.nanosecond / 1_000_000,
+		)
+	}
+}
+
+impl fmt::Display for UtcTime {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+			self.year,
+			self.month,
+			self.day,
+			self.hour,
+			self.minute,
+			self.second,
+		)
+	}
+}
+
+#[macro_export]
+/// Get the current [`UtcTime`](crate::time::UtcTime)
+///
+/// ```rust
+/// # use benri::time::*;
+/// let utc = utc!();
+///
+/// assert!(utc.year >= 2023);
+/// ```
+macro_rules! utc {
+	() => {
+		$crate::time::UtcTime::now()
+	}
+}
+pub use utc;
+
+#[macro_export]
+/// Get the current UTC time as an RFC 3339 / ISO 8601 [`String`]
+///
+/// Pass `millis` to include milliseconds.
+///
+/// ```rust
+/// # use benri::time::*;
+/// let s = iso8601!();
+/// assert!(s.len() == "2023-04-02T18:31:27Z".len());
+/// assert!(s.ends_with('Z'));
+///
+/// let s = iso8601!(millis);
+/// assert!(s.len() == "2023-04-02T18:31:27.123Z".len());
+/// ```
+macro_rules! iso8601 {
+	() => {
+		::std::string::ToString::to_string(&$crate::time::UtcTime::now())
+	};
+	(millis) => {
+		$crate::time::UtcTime::now().to_string_millis()
+	};
+}
+pub use iso8601;
+
+//---------------------------------------------------------------------------------------------------- Deadline
+#[derive(Copy,Clone,Debug,Default,PartialEq,Eq,PartialOrd,Ord,Hash)]
+/// Error returned when a [`Deadline`] expires before a condition is met
+pub struct TimedOut;
+
+impl fmt::Display for TimedOut {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("deadline expired")
+	}
+}
+
+impl std::error::Error for TimedOut {}
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq,PartialOrd,Ord,Hash)]
+/// A point in time after which something should give up
+///
+/// ```rust
+/// # use benri::time::*;
+/// let deadline = deadline!(std::time::Duration::from_millis(10));
+/// assert!(!deadline.expired());
+///
+/// deadline.sleep_until();
+/// assert!(deadline.expired());
+/// assert!(deadline.remaining() == std::time::Duration::ZERO);
+/// ```
+pub struct Deadline {
+	at: Instant,
+}
+
+impl Deadline {
+	#[inline]
+	/// Create a [`Deadline`] that expires `duration` from now
+	pub fn after(duration: Duration) -> Self {
+		Self { at: crate::now!() + duration }
+	}
+
+	#[inline]
+	/// Create a [`Deadline`] that expires at `instant`
+	pub const fn at(instant: Instant) -> Self {
+		Self { at: instant }
+	}
+
+	#[inline]
+	/// Return the [`Instant`] this [`Deadline`] expires at
+	pub const fn instant(&self) -> Instant {
+		self.at
+	}
+
+	#[inline]
+	/// Returns `true` if the [`Deadline`] has passed
+	pub fn expired(&self) -> bool {
+		crate::now!() >= self.at
+	}
+
+	#[inline]
+	/// Time left until the [`Deadline`], or [`Duration::ZERO`] if it has passed
+	pub fn remaining(&self) -> Duration {
+		self.at.saturating_duration_since(crate::now!())
+	}
+
+	#[inline]
+	/// Sleep the current thread until the [`Deadline`] has passed
+	pub fn sleep_until(&self) {
+		let remaining = self.remaining();
+		if !remaining.is_zero() {
+			clock_sleep(remaining);
+		}
+	}
+
+	/// Call `f` every `interval` until it returns [`Some`] or the [`Deadline`] passes
+	///
+	/// `f` is always called at least once, and once more after the last sleep.
+	///
+	/// # Errors
+	/// Returns [`TimedOut`] if the [`Deadline`] passed before `f` returned [`Some`].
+	pub fn poll_until<T, F>(&self, interval: Duration, mut f: F) -> Result<T, TimedOut>
+	where
+		F: FnMut() -> Option<T>,
+	{
+		loop {
+			if let Some(t) = f() {
+				return Ok(t);
+			}
+
+			let remaining = self.remaining();
+			if remaining.is_zero() {
+				return Err(TimedOut);
+			}
+
+			clock_sleep(remaining.min(interval));
+		}
+	}
+}
+
+#[macro_export]
+/// Create a [`Deadline`](crate::time::Deadline) that expires after a [`std::time::Duration`]
+///
+/// ```rust
+/// # use benri::time::*;
+/// let deadline = deadline!(second!());
+///
+/// assert!(!deadline.expired());
+/// assert!(deadline.remaining() <= second!());
+/// ```
+macro_rules! deadline {
+	($duration:expr) => {
+		$crate::time::Deadline::after($duration)
+	}
+}
+pub use deadline;
+
+#[macro_export]
+/// Poll a closure every `interval` until it returns [`Some`] or the [`Deadline`](crate::time::Deadline) passes
+///
+/// This returns [`Result<T, TimedOut>`](crate::time::TimedOut).
+///
+/// For plain `bool` conditions, use [`bool::then_some`].
+///
+/// ```rust
+/// # use benri::time::*;
+/// # use std::time::Duration;
+/// let now = now!();
+///
+/// // Succeeds after ~20 milliseconds.
+/// let deadline = deadline!(second!());
+/// let r = poll_until!(deadline, Duration::from_millis(5), || {
+///     (millis!(now) >= 20).then_some(123)
+/// });
+/// assert_eq!(r, Ok(123));
+///
+/// // Never succeeds.
+/// let deadline = deadline!(Duration::from_millis(20));
+/// let r: Result<(), TimedOut> = poll_until!(deadline, Duration::from_millis(5), || None);
+/// assert_eq!(r, Err(TimedOut));
+/// ```
+macro_rules! poll_until {
+	($deadline:expr, $interval:expr, $f:expr) => {
+		$deadline.poll_until($interval, $f)
+	}
+}
+pub use poll_until;
+
+//---------------------------------------------------------------------------------------------------- Ticker
+#[derive(Copy,Clone,Debug,Default,PartialEq,Eq,PartialOrd,Ord,Hash)]
+/// What a [`Ticker`] does when a tick is missed
+pub enum MissedTick {
+	/// Fire the missed ticks immediately, one per [`Ticker::tick`], until caught up
+	Burst,
+	#[default]
+	/// Drop the missed ticks and keep the original schedule
+	Skip,
+	/// Drop the missed ticks and restart the schedule from now
+	Delay,
+}
+
+#[derive(Clone,Debug)]
+/// Pace a loop at a fixed interval
+///
+/// Unlike calling [`sleep!`](crate::thread::sleep) at the end of every loop,
+/// [`Ticker::tick`] only sleeps for what is left of the current interval,
+/// so the time spent working does not cause the loop to drift.
+///
+/// The first tick happens `interval` after the [`Ticker`] is created.
+///
+/// ```rust
+/// # use benri::time::*;
+/// # use std::time::Duration;
+/// let now = now!();
+/// let mut ticker = Ticker::new(Duration::from_millis(10));
+///
+/// for _ in 0..5 {
+///     // Work.
+///     std::thread::sleep(Duration::from_millis(5));
+///     ticker.tick();
+/// }
+///
+/// // ~50 milliseconds (not ~75), the work is accounted for.
+/// assert!(millis!(now) >= 50);
+/// ```
+pub struct Ticker {
+	interval: Duration,
+	next: Instant,
+	policy: MissedTick,
+	missed: u64,
+}
+
+impl Ticker {
+	#[inline]
+	/// Create a [`Ticker`] with the default [`MissedTick::Skip`] policy
+	pub fn new(interval: Duration) -> Self {
+		Self {
+			interval,
+			next: crate::now!() + interval,
+			policy: MissedTick::default(),
+			missed: 0,
+		}
+	}
+
+	#[inline]
+	#[must_use]
+	/// Set the [`MissedTick`] policy
+	pub const fn policy(mut self, policy: MissedTick) -> Self {
+		self.policy = policy;
+		self
+	}
+
+	#[inline]
+	/// Return the interval of this [`Ticker`]
+	pub const fn interval(&self) -> Duration {
+		self.interval
+	}
+
+	#[inline]
+	/// Total amount of ticks dropped so far
+	///
+	/// This is always `0` with [`MissedTick::Burst`], as no ticks are dropped.
+	pub const fn missed(&self) -> u64 {
+		self.missed
+	}
+
+	/// Sleep until the next tick
+	///
+	/// This returns how many whole intervals behind schedule this
+	/// tick is, which is `0` if the loop is keeping up.
+	///
+	/// If the tick was already missed, this does not sleep, and the
+	/// next tick is scheduled according to the [`MissedTick`] policy.
+	///
+	/// ```rust
+	/// # use benri::time::*;
+	/// # use std::sync::Arc;
+	/// let clock = MockClock::new();
+	/// set_thread_clock(Some(Arc::new(clock.clone())));
+	///
+	/// // Miss more than `u32::MAX` ticks.
+	/// let mut ticker = Ticker::new(dur!(1 ns));
+	/// clock.advance(dur!(5 s));
+	/// assert!(ticker.tick() == 4_999_999_999);
+	///
+	/// // Back on schedule.
+	/// assert!(ticker.tick() == 0);
+	///
+	/// set_thread_clock(None);
+	/// ```
+	pub fn tick(&mut self) -> u64 {
+		let now = crate::now!();
+
+		if now < self.next {
+			clock_sleep(self.next - now);
+			self.next += self.interval;
+			return 0;
+		}
+
+		let missed = match self.interval.as_nanos() {
+			0 => 0,
+			i => u64::try_from((now - self.next).as_nanos() / i).unwrap_or(u64::MAX),
+		};
+
+		match self.policy {
+			// These ticks will still fire, so they are not counted as missed.
+			MissedTick::Burst => self.next += self.interval,
+			MissedTick::Skip  => {
+				self.missed = self.missed.saturating_add(missed);
+				// Jump over the missed ticks in nanoseconds, so a huge
+				// amount of them can't truncate or overflow the schedule.
+				self.next = self.interval.as_nanos()
+					.checked_mul(u128::from(missed) + 1)
+					.and_then(|nanos| u64::try_from(nanos).ok())
+					.and_then(|nanos| self.next.checked_add(Duration::from_nanos(nanos)))
+					.unwrap_or(now + self.interval);
+			},
+			MissedTick::Delay => {
+				self.missed = self.missed.saturating_add(missed);
+				self.next = now + self.interval;
+			},
+		}
+
+		missed
+	}
+}
+
+#[macro_export]
+/// Create a [`Ticker`](crate::time::Ticker) with a [`std::time::Duration`] interval
+///
+/// ```rust
+/// # use benri::time::*;
+/// let ticker = ticker!(second!());
+/// assert!(ticker.interval() == second!());
+/// ```
+macro_rules! ticker {
+	($interval:expr) => {
+		$crate::time::Ticker::new($interval)
+	}
+}
+pub use ticker;
+
+#[macro_export]
+/// Sleep until the next tick of a [`Ticker`](crate::time::Ticker)
+///
+/// This returns how many ticks were missed, see [`Ticker::tick`](crate::time::Ticker::tick).
+///
+/// ```rust
+/// # use benri::time::*;
+/// # use std::time::Duration;
+/// let mut ticker = ticker!(Duration::from_millis(10));
+///
+/// // Fell behind by ~3 ticks.
+/// std::thread::sleep(Duration::from_millis(45));
+/// assert!(tick!(ticker) >= 3);
+///
+/// // Caught up.
+/// assert!(tick!(ticker) == 0);
+/// ```
+macro_rules! tick {
+	($ticker:expr) => {
+		$ticker.tick()
+	}
+}
+pub use tick;
+
+//---------------------------------------------------------------------------------------------------- RateLimiter
+#[derive(Debug)]
+struct Bucket {
+	tokens: u32,
+	last: Instant,
+}
+
+#[derive(Clone,Debug)]
+/// A token-bucket rate limiter
+///
+/// The bucket holds up to `capacity` tokens and starts full.
+/// One token is added back every `refill` interval.
+///
+/// A `capacity` of `0` is treated as `1`, since an empty bucket could never be acquired from.
+///
+/// This is cheaply [`Clone`]-able and all clones share the same bucket,
+/// so it can be handed out to multiple threads.
+///
+/// ```rust
+/// # use benri::time::*;
+/// # use std::time::Duration;
+/// let limiter = RateLimiter::new(2, Duration::from_millis(20));
+///
+/// assert!(limiter.try_acquire());
+/// assert!(limiter.try_acquire());
+/// assert!(!limiter.try_acquire());
+///
+/// let now = now!();
+/// let l = limiter.clone();
+/// std::thread::spawn(move || l.acquire()).join().unwrap();
+/// assert!(millis!(now) >= 10);
+///
+/// // A `capacity` of `0` is clamped to `1`.
+/// let limiter = RateLimiter::new(0, Duration::from_millis(20));
+/// assert!(limiter.capacity() == 1);
+/// assert!(limiter.try_acquire());
+/// ```
+pub struct RateLimiter {
+	capacity: u32,
+	refill: Duration,
+	bucket: Arc<Mutex<Bucket>>,
+}
+
+impl RateLimiter {
+	/// Create a full [`RateLimiter`] holding up to `capacity` tokens, refilling one every `refill`
+	///
+	/// `capacity` is clamped to at least `1`.
+	pub fn new(capacity: u32, refill: Duration) -> Self {
+		let capacity = capacity.max(1);
+		let bucket = Bucket {
+			tokens: capacity,
+			last: crate::now!(),
+		};
+
+		Self {
+			capacity,
+			refill,
+			bucket: crate::arc_mut!(bucket),
+		}
+	}
+
+	// Add tokens for the time elapsed since the last refill.
+	fn refill(&self, bucket: &mut Bucket, now: Instant) {
+		if bucket.tokens >= self.capacity {
+			bucket.last = now;
+			return;
+		}
+
+		let elapsed = now.saturating_duration_since(bucket.last);
+		let added = match self.refill.as_nanos() {
+			0 => u128::from(self.capacity),
+			r => elapsed.as_nanos() / r,
+		};
+		let added = u32::try_from(added).unwrap_or(u32::MAX);
+
+		if added == 0 {
+			return;
+		}
+
+		bucket.tokens = bucket.tokens.saturating_add(added).min(self.capacity);
+		if bucket.tokens == self.capacity {
+			bucket.last = now;
+		} else {
+			bucket.last += self.refill * added;
+		}
+	}
+
+	/// Take a token if one is available, returns `false` if not
+	pub fn try_acquire(&self) -> bool {
+		let mut bucket = self.bucket.lock().unwrap();
+		self.refill(&mut bucket, crate::now!());
+
+		if bucket.tokens == 0 {
+			false
+		} else {
+			bucket.tokens -= 1;
+			true
+		}
+	}
+
+	/// Take a token, sleeping the current thread until one is available
+	pub fn acquire(&self) {
+		loop {
+			let wait = {
+				let mut bucket = self.bucket.lock().unwrap();
+				let now = crate::now!();
+				self.refill(&mut bucket, now);
+
+				if bucket.tokens > 0 {
+					bucket.tokens -= 1;
+					return;
+				}
+
+				self.refill.saturating_sub(now.saturating_duration_since(bucket.last))
+			};
+
+			clock_sleep(wait);
+		}
+	}
+
+	/// Amount of tokens currently available
+	pub fn available(&self) -> u32 {
+		let mut bucket = self.bucket.lock().unwrap();
+		self.refill(&mut bucket, crate::now!());
+		bucket.tokens
+	}
+
+	#[inline]
+	/// Maximum amount of tokens this [`RateLimiter`] holds
+	pub const fn capacity(&self) -> u32 {
+		self.capacity
+	}
+}
+
+//---------------------------------------------------------------------------------------------------- Bench
+#[derive(Clone,Debug,PartialEq,Eq)]
+/// The result of a micro-benchmark, see [`bench!`](crate::time::bench)
+///
+/// All statistics are per-iteration and return [`Duration::ZERO`] if there are no samples.
+///
+/// [`std::fmt::Display`] formats this as a table with a header.
+///
+/// ```rust
+/// # use benri::time::*;
+/// let r = BenchResult::run("sum", 100, || {
+///     let v: u64 = (0..100).sum();
+///     std::hint::black_box(v);
+/// });
+///
+/// assert!(r.samples().len() == 100);
+/// assert!(r.min() <= r.median());
+/// assert!(r.median() <= r.max());
+/// assert!(r.percentile(100.0) == r.max());
+/// println!("{r}");
+/// ```
+pub struct BenchResult {
+	name: String,
+	// Sorted.
+	samples: Vec<Duration>,
+}
+
+impl BenchResult {
+	/// Benchmark `f` by timing it `iterations` times
+	///
+	/// `f` is called `iterations / 10` times (at least once) beforehand as a warm-up.
+	///
+	/// Each iteration is timed individually, so very fast
+	/// functions will include the overhead of [`Instant::now`].
+	pub fn run<S, F>(name: S, iterations: usize, mut f: F) -> Self
+	where
+		S: Into<String>,
+		F: FnMut(),
+	{
+		for _ in 0..(iterations / 10).max(1) {
+			f();
+		}
+
+		// This always uses the real clock, not a `Clock` override.
+		let mut samples = Vec::with_capacity(iterations);
+		for _ in 0..iterations {
+			let now = Instant::now();
+			f();
+			samples.push(now.elapsed());
+		}
+		samples.sort_unstable();
+
+		Self {
+			name: name.into(),
+			samples,
+		}
+	}
+
+	#[inline]
+	/// The name of this benchmark
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	#[inline]
+	/// All samples, sorted from fastest to slowest
+	pub fn samples(&self) -> &[Duration] {
+		&self.samples
+	}
+
+	#[inline]
+	/// Fastest sample
+	pub fn min(&self) -> Duration {
+		self.samples.first().copied().unwrap_or_default()
+	}
+
+	#[inline]
+	/// Slowest sample
+	pub fn max(&self) -> Duration {
+		self.samples.last().copied().unwrap_or_default()
+	}
+
+	/// Mean of all samples
+	pub fn mean(&self) -> Duration {
+		if self.samples.is_empty() {
+			return Duration::ZERO;
+		}
+		let total: Duration = self.samples.iter().sum();
+		total / self.samples.len() as u32
+	}
+
+	#[inline]
+	/// Median of all samples
+	pub fn median(&self) -> Duration {
+		self.percentile(50.0)
+	}
+
+	/// Sample standard deviation
+	pub fn stddev(&self) -> Duration {
+		let len = self.samples.len();
+		if len < 2 {
+			return Duration::ZERO;
+		}
+
+		let mean = self.mean().as_secs_f64();
+		let variance = self.samples.iter()
+			.map(|s| (s.as_secs_f64() - mean).powi(2))
+			.sum::<f64>() / (len - 1) as f64;
+
+		Duration::from_secs_f64(variance.sqrt())
+	}
+
+	/// The `p`th percentile (`0.0..=100.0`) using the nearest-rank method
+	///
+	/// `p` is clamped to `0.0..=100.0`.
+	pub fn percentile(&self, p: f64) -> Duration {
+		if self.samples.is_empty() {
+			return Duration::ZERO;
+		}
+
+		let p = p.clamp(0.0, 100.0) / 100.0;
+		let index = (p * (self.samples.len() - 1) as f64).round() as usize;
+		self.samples[index]
+	}
+
+	/// Print this result as a table
+	///
+	/// If the `log` feature flag is enabled, this uses `ok!()`, else [`std::println`].
+	pub fn report(&self) {
+		#[cfg(feature = "log")]
+		for line in self.to_string().lines() {
+			crate::ok!("{line}");
+		}
+		#[cfg(not(feature = "log"))]
+		::std::println!("{self}");
+	}
+}
+
+impl fmt::Display for BenchResult {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(
+			f,
+			"{:<20} | {:>8} | {:>12} | {:>12} | {:>12} | {:>12} | {:>12} | {:>12}",
+			"name", "samples", "min", "median", "mean", "stddev", "p99", "max",
+		)?;
+		write!(
+			f,
+			"{:<20} | {:>8} | {:>12.2?} | {:>12.2?} | {:>12.2?} | {:>12.2?} | {:>12.2?} | {:>12.2?}",
+			self.name,
+			self.samples.len(),
+			self.min(),
+			self.median(),
+			self.mean(),
+			self.stddev(),
+			self.percentile(99.0),
+			self.max(),
+		)
+	}
+}
+
+#[macro_export]
+/// Micro-benchmark a block of code
+///
+/// This runs [`BenchResult::run`](crate::time::BenchResult::run) with the block
+/// passed through [`black_box!`](crate::hint::black_box), reports the result with
+/// [`BenchResult::report`](crate::time::BenchResult::report), then returns it.
+///
+/// ```rust
+/// # use benri::time::*;
+/// let v = vec![1_u64; 1000];
+///
+/// let r = bench!("sum", 50, {
+///     v.iter().sum::<u64>()
+/// });
+///
+/// assert!(r.name() == "sum");
+/// assert!(r.samples().len() == 50);
+/// ```
+macro_rules! bench {
+	($name:expr, $iterations:expr, $block:block) => {{
+		let result = $crate::time::BenchResult::run($name, $iterations, || {
+			$crate::black_box!($block);
+		});
+		result.report();
+		result
+	}}
+}
+pub use bench;
+
+//---------------------------------------------------------------------------------------------------- Clock
+/// A source of time
+///
+/// By default, all `time` macros and types use the real system clock.
+///
+/// A [`Clock`] can be set for the current thread with [`set_thread_clock`]
+/// or for all threads with [`set_global_clock`], after which [`now!`](crate::time::now),
+/// [`unix!`](crate::time::unix), [`sleep!`](crate::thread::sleep) and friends, and
+/// types like [`Deadline`], [`Stopwatch`] and [`Ticker`] will use it instead.
+///
+/// The thread clock takes priority over the global clock.
+///
+/// [`BenchResult`] always uses the real clock.
+pub trait Clock: Send + Sync {
+	/// The current [`Instant`]
+	fn now(&self) -> Instant;
+	/// The current [`SystemTime`]
+	fn system_time(&self) -> SystemTime;
+	/// Sleep the current thread for `duration`
+	fn sleep(&self, duration: Duration);
+}
+
+#[derive(Copy,Clone,Debug,Default,PartialEq,Eq,PartialOrd,Ord,Hash)]
+/// The real system [`Clock`]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+	#[inline]
+	fn now(&self) -> Instant {
+		Instant::now()
+	}
+
+	#[inline]
+	fn system_time(&self) -> SystemTime {
+		SystemTime::now()
+	}
+
+	#[inline]
+	fn sleep(&self, duration: Duration) {
+		std::thread::sleep(duration);
+	}
+}
+
+#[derive(Debug)]
+struct MockState {
+	instant: Instant,
+	system_time: SystemTime,
+}
+
+#[derive(Clone,Debug)]
+/// A [`Clock`] that only moves when told to
+///
+/// Sleeping on a [`MockClock`] advances it instead of blocking.
+///
+/// This is cheaply [`Clone`]-able and all clones share the same time,
+/// so a test can keep a handle to a clock it has set as the override.
+///
+/// ```rust
+/// # use benri::time::*;
+/// # use std::sync::Arc;
+/// let clock = MockClock::new();
+/// set_thread_clock(Some(Arc::new(clock.clone())));
+///
+/// let now = now!();
+/// let deadline = deadline!(dur!(5 s));
+///
+/// clock.advance(dur!(4 s));
+/// assert!(secs!(now) == 4);
+/// assert!(!deadline.expired());
+///
+/// // This returns instantly.
+/// benri::sleep!(1);
+/// assert!(deadline.expired());
+///
+/// set_thread_clock(None);
+/// ```
+pub struct MockClock {
+	state: Arc<Mutex<MockState>>,
+}
+
+impl MockClock {
+	/// Create a [`MockClock`] starting at the current real time
+	pub fn new() -> Self {
+		let state = MockState {
+			instant: Instant::now(),
+			system_time: SystemTime::now(),
+		};
+		Self { state: crate::arc_mut!(state) }
+	}
+
+	/// Create a [`MockClock`] whose [`SystemTime`] starts at `secs` after [`std::time::UNIX_EPOCH`]
+	pub fn at_unix(secs: u64) -> Self {
+		let clock = Self::new();
+		clock.state.lock().unwrap().system_time = SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+		clock
+	}
+
+	/// Move the clock forwards by `duration`
+	pub fn advance(&self, duration: Duration) {
+		let mut state = self.state.lock().unwrap();
+		state.instant     += duration;
+		state.system_time += duration;
+	}
+}
+
+impl Default for MockClock {
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Clock for MockClock {
+	#[inline]
+	fn now(&self) -> Instant {
+		self.state.lock().unwrap().instant
+	}
+
+	#[inline]
+	fn system_time(&self) -> SystemTime {
+		self.state.lock().unwrap().system_time
+	}
+
+	#[inline]
+	fn sleep(&self, duration: Duration) {
+		self.advance(duration);
+	}
+}
+
+// Amount of clock overrides currently set, so the
+// common case of no overrides is a single atomic load.
+static OVERRIDES: AtomicUsize = AtomicUsize::new(0);
+static GLOBAL_CLOCK: RwLock<Option<Arc<dyn Clock>>> = RwLock::new(None);
+thread_local! {
+	static THREAD_CLOCK: ThreadClock = const { ThreadClock(RefCell::new(None)) };
+}
+
+// The thread's `Clock` override, which is
+// uncounted from `OVERRIDES` when the thread exits.
+struct ThreadClock(RefCell<Option<Arc<dyn Clock>>>);
+
+impl Drop for ThreadClock {
+	fn drop(&mut self) {
+		count_override(self.0.get_mut(), &None);
+	}
+}
+
+// Keep `OVERRIDES` in sync when replacing `old` with `new`.
+fn count_override(old: &Option<Arc<dyn Clock>>, new: &Option<Arc<dyn Clock>>) {
+	match (old.is_some(), new.is_some()) {
+		(false, true) => { OVERRIDES.fetch_add(1, Ordering::SeqCst); },
+		(true, false) => { OVERRIDES.fetch_sub(1, Ordering::SeqCst); },
+		_ => (),
+	}
+}
+
+/// Set (or with [`None`], unset) the [`Clock`] override for all threads, returning the previous one
+pub fn set_global_clock(clock: Option<Arc<dyn Clock>>) -> Option<Arc<dyn Clock>> {
+	let mut global = GLOBAL_CLOCK.write().unwrap();
+	count_override(&global, &clock);
+	std::mem::replace(&mut global, clock)
+}
+
+/// Set (or with [`None`], unset) the [`Clock`] override for the current thread, returning the previous one
+pub fn set_thread_clock(clock: Option<Arc<dyn Clock>>) -> Option<Arc<dyn Clock>> {
+	THREAD_CLOCK.with(|c| {
+		let mut c = c.0.borrow_mut();
+		count_override(&c, &clock);
+		std::mem::replace(&mut *c, clock)
+	})
+}
+
+// Call `f` with the current `Clock` override, if any.
+fn with_override<T>(f: impl FnOnce(&dyn Clock) -> T) -> Option<T> {
+	if OVERRIDES.load(Ordering::Relaxed) == 0 {
+		return None;
+	}
+
+	if let Some(clock) = THREAD_CLOCK.try_with(|c| c.0.borrow().clone()).ok().flatten() {
+		return Some(f(&*clock));
+	}
+
+	let clock = GLOBAL_CLOCK.read().unwrap().clone();
+	clock.map(|clock| f(&*clock))
+}
+
+#[inline]
+/// The current [`Instant`], from the [`Clock`] override if set
+pub fn clock_now() -> Instant {
+	with_override(|c| c.now()).unwrap_or_else(Instant::now)
+}
+
+#[inline]
+/// The current [`SystemTime`], from the [`Clock`] override if set
+pub fn clock_system_time() -> SystemTime {
+	with_override(|c| c.system_time()).unwrap_or_else(SystemTime::now)
+}
+
+#[inline]
+/// Sleep the current thread for `duration`, with the [`Clock`] override if set
+pub fn clock_sleep(duration: Duration) {
+	if with_override(|c| c.sleep(duration)).is_none() {
+		std::thread::sleep(duration);
+	}
+}
+
+#[inline]
+/// Time elapsed since `instant`, according to [`clock_now`]
+///
+/// This is [`Instant::elapsed`], unless a [`Clock`] override is set.
+pub fn elapsed(instant: Instant) -> Duration {
+	clock_now().saturating_duration_since(instant)
+}
+
+//---------------------------------------------------------------------------------------------------- Retry
+#[derive(Copy,Clone,Debug,PartialEq,Eq,PartialOrd,Ord,Hash)]
+enum BackoffKind {
+	Constant,
+	Linear,
+	Exponential,
+}
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq,PartialOrd,Ord,Hash)]
+/// How long to wait between attempts, see [`retry!`](crate::time::retry)
+///
+/// ```rust
+/// # use benri::time::*;
+/// let b = Backoff::exponential(dur!(100 ms)).max(dur!(1 s));
+///
+/// assert!(b.delay(1) == dur!(100 ms));
+/// assert!(b.delay(2) == dur!(200 ms));
+/// assert!(b.delay(3) == dur!(400 ms));
+/// assert!(b.delay(5) == dur!(1 s));
+///
+/// let b = Backoff::linear(dur!(100 ms));
+/// assert!(b.delay(3) == dur!(300 ms));
+///
+/// let b = Backoff::constant(dur!(100 ms)).jitter(true);
+/// assert!(b.delay(3) >= dur!(50 ms));
+/// assert!(b.delay(3) <= dur!(100 ms));
+/// ```
+pub struct Backoff {
+	kind: BackoffKind,
+	base: Duration,
+	max: Duration,
+	jitter: bool,
+}
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+/// The last error returned by [`Backoff::retry`], and how many attempts were made
+pub struct RetryError<E> {
+	/// The error from the last attempt
+	pub error: E,
+	/// How many attempts were made
+	pub attempts: u32,
+}
+
+impl<E: fmt::Display> fmt::Display for RetryError<E> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} (after {} attempts)", self.error, self.attempts)
+	}
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for RetryError<E> {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		Some(&self.error)
+	}
+}
+
+impl Backoff {
+	#[inline]
+	/// Wait `base` between every attempt
+	pub const fn constant(base: Duration) -> Self {
+		Self { kind: BackoffKind::Constant, base, max: Duration::MAX, jitter: false }
+	}
+
+	#[inline]
+	/// Wait `base * n` after the `n`th failed attempt
+	pub const fn linear(base: Duration) -> Self {
+		Self { kind: BackoffKind::Linear, base, max: Duration::MAX, jitter: false }
+	}
+
+	#[inline]
+	/// Wait `base * 2^(n - 1)` after the `n`th failed attempt
+	pub const fn exponential(base: Duration) -> Self {
+		Self { kind: BackoffKind::Exponential, base, max: Duration::MAX, jitter: false }
+	}
+
+	#[inline]
+	#[must_use]
+	/// Never wait longer than `max`
+	pub const fn max(mut self, max: Duration) -> Self {
+		self.max = max;
+		self
+	}
+
+	#[inline]
+	#[must_use]
+	/// Randomly shorten each wait to somewhere between `50%` and `100%` of itself
+	pub const fn jitter(mut self, jitter: bool) -> Self {
+		self.jitter = jitter;
+		self
+	}
+
+	/// How long to wait after the `n`th failed attempt (starting at `1`)
+	pub fn delay(&self, n: u32) -> Duration {
+		let n = n.max(1);
+
+		let delay = match self.kind {
+			BackoffKind::Constant    => self.base,
+			BackoffKind::Linear      => self.base.saturating_mul(n),
+			BackoffKind::Exponential => self.base.saturating_mul(2_u32.saturating_pow(n - 1)),
+		}.min(self.max);
+
+		if self.jitter {
+			use std::hash::{BuildHasher,Hasher};
+			// `RandomState` is randomly seeded, which is good enough for jitter.
+			let random = std::collections::hash_map::RandomState::new().build_hasher().finish();
+			let half = delay / 2;
+			half + Duration::from_nanos(random % (half.as_nanos() as u64).saturating_add(1))
+		} else {
+			delay
+		}
+	}
+
+	/// Call `f` up to `attempts` times until it returns [`Ok`], waiting between each failure
+	///
+	/// `f` is always called at least once.
+	///
+	/// If the `log` feature flag is enabled, each failed attempt is logged
+	/// with `skip_warn!()`, and the final failure with `fail!()`.
+	/// Only the attempt count and delay are logged, not the error itself.
+	///
+	/// # Errors
+	/// If every attempt fails, the last error is returned along with the attempt count.
+	pub fn retry<T, E, F>(&self, attempts: u32, mut f: F) -> Result<T, RetryError<E>>
+	where
+		F: FnMut() -> Result<T, E>,
+	{
+		let attempts = attempts.max(1);
+		let mut attempt = 1;
+
+		loop {
+			match f() {
+				Ok(t) => return Ok(t),
+				Err(error) if attempt >= attempts => {
+					#[cfg(feature = "log")]
+					crate::fail!("attempt {attempt}/{attempts} failed, giving up");
+					return Err(RetryError { error, attempts: attempt });
+				},
+				Err(_) => {
+					let delay = self.delay(attempt);
+					#[cfg(feature = "log")]
+					crate::skip_warn!("attempt {attempt}/{attempts} failed, retrying in {delay:?}");
+					clock_sleep(delay);
+					attempt += 1;
+				},
+			}
+		}
+	}
+}
+
+#[macro_export]
+/// Retry a fallible closure with a [`Backoff`](crate::time::Backoff)
+///
+/// This calls [`Backoff::retry`](crate::time::Backoff::retry), returning the first
+/// [`Ok`], or the last [`Err`] in a [`RetryError`](crate::time::RetryError).
+///
+/// ```rust
+/// # use benri::time::*;
+/// let mut n = 0;
+/// let r = retry!(5, Backoff::constant(dur!(1 ms)), || {
+///     n += 1;
+///     if n < 3 { Err("not yet") } else { Ok(n) }
+/// });
+/// assert_eq!(r, Ok(3));
+///
+/// let r: Result<(), _> = retry!(3, Backoff::exponential(dur!(1 ms)), || Err("never"));
+/// assert_eq!(r, Err(RetryError { error: "never", attempts: 3 }));
+///
+/// // The error type doesn't need to implement `Display`.
+/// #[derive(Debug,PartialEq)]
+/// struct MyError;
+///
+/// let r: Result<(), _> = retry!(2, Backoff::constant(dur!(1 ms)), || Err(MyError));
+/// assert_eq!(r, Err(RetryError { error: MyError, attempts: 2 }));
+/// ```
+macro_rules! retry {
+	($attempts:expr, $backoff:expr, $f:expr) => {
+		$backoff.retry($attempts, $f)
+	}
+}
+pub use retry;